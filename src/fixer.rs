@@ -0,0 +1,358 @@
+use crate::lint::{Checked, CheckedState, RuleSet, Sources};
+use anyhow::{format_err, Context, Error};
+use similar::TextDiff;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// -------------------------------------------------------------------------------------------------
+// Fixer
+// -------------------------------------------------------------------------------------------------
+
+pub struct Fixer;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Fixed {
+    pub applied: usize,
+    /// Rules of the conflicted fixes
+    pub conflicted: Vec<String>,
+    /// Regions in the fixed source rewritten by each rule
+    pub rewritten: Vec<(String, usize, usize)>,
+}
+
+const MAX_FIX_PASS: usize = 10;
+
+impl Fixer {
+    /// Applies fixes on memory repeatedly until no conflicted fix remains.
+    /// Returns the results re-checked against the fixed sources.
+    pub fn run(
+        ruleset: &RuleSet,
+        mut checked: Vec<Checked>,
+        mut sources: Sources,
+        report_unused: bool,
+    ) -> Result<(Vec<Checked>, Sources), Error> {
+        // Conflicted fixes are retried after applying the others.
+        // Only the conflicted rules are retried, and a region rewritten by a rule is not rewritten by
+        // the same rule again even if the replacement still fails.
+        let mut retry: Option<HashMap<PathBuf, Fixed>> = None;
+        for _ in 0..MAX_FIX_PASS {
+            let mut applied = 0;
+            let mut conflicted = 0;
+            let mut fixed_sources = Vec::new();
+            let mut next = HashMap::new();
+            for (path, src, dst, fixed) in Fixer::gen_fixed(&checked, &sources, retry.as_ref())? {
+                if src != dst {
+                    fixed_sources.push((path.clone(), dst));
+                }
+                applied += fixed.applied;
+                conflicted += fixed.conflicted.len();
+                next.insert(path, fixed);
+            }
+
+            if applied == 0 {
+                break;
+            }
+
+            for (path, dst) in fixed_sources {
+                let (mut rechecked, fixed_source) =
+                    ruleset.check_source(&path, dst, report_unused)?;
+                checked.retain(|x| x.path != path);
+                checked.append(&mut rechecked);
+                sources.extend(fixed_source);
+            }
+            checked.sort_by(|x, y| x.path.cmp(&y.path));
+
+            if conflicted == 0 {
+                break;
+            }
+            retry = Some(next);
+        }
+        Ok((checked, sources))
+    }
+
+    #[cfg_attr(tarpaulin, skip)]
+    pub fn write(original: &Sources, fixed: &Sources) -> Result<(), Error> {
+        for (path, src, dst) in Fixer::collect_changed(original, fixed) {
+            if src != dst {
+                fs::write(&path, dst)
+                    .with_context(|| format!("failed to write: '{}'", path.to_string_lossy()))?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn diff(original: &Sources, fixed: &Sources) -> String {
+        let mut ret = String::new();
+        for (path, src, dst) in Fixer::collect_changed(original, fixed) {
+            ret.push_str(&Fixer::gen_diff(&path, src, dst));
+        }
+        ret
+    }
+
+    fn collect_changed<'a>(
+        original: &'a Sources,
+        fixed: &'a Sources,
+    ) -> Vec<(PathBuf, &'a str, &'a str)> {
+        let mut ret: Vec<_> = original
+            .iter()
+            .filter_map(|(path, src)| {
+                fixed
+                    .get(path)
+                    .filter(|dst| *dst != src)
+                    .map(|dst| (path.clone(), src.as_str(), dst.as_str()))
+            })
+            .collect();
+        ret.sort_unstable_by(|x, y| x.0.cmp(&y.0));
+        ret
+    }
+
+    fn gen_fixed<'a>(
+        checked: &[Checked],
+        sources: &'a Sources,
+        retry: Option<&HashMap<PathBuf, Fixed>>,
+    ) -> Result<Vec<(PathBuf, &'a str, String, Fixed)>, Error> {
+        let mut ret = Vec::new();
+        for (path, fixes) in Fixer::collect_by_path(checked, retry) {
+            let src = sources
+                .get(&path)
+                .ok_or_else(|| format_err!("source not found: '{}'", path.to_string_lossy()))?;
+
+            let (dst, fixed) = Fixer::apply(src, &fixes);
+            ret.push((path, src.as_str(), dst, fixed));
+        }
+        Ok(ret)
+    }
+
+    fn gen_diff(path: &Path, src: &str, dst: &str) -> String {
+        if src == dst {
+            return String::new();
+        }
+
+        let path = path.to_string_lossy().replace('\\', "/");
+        TextDiff::from_lines(src, dst)
+            .unified_diff()
+            .header(&format!("a/{}", path), &format!("b/{}", path))
+            .to_string()
+    }
+
+    fn collect_by_path<'a>(
+        checked: &'a [Checked],
+        retry: Option<&HashMap<PathBuf, Fixed>>,
+    ) -> Vec<(PathBuf, Vec<&'a Checked>)> {
+        let mut map: HashMap<PathBuf, Vec<&Checked>> = HashMap::new();
+        for c in checked {
+            if c.state == CheckedState::Fail && c.replace.is_some() && Fixer::is_retried(c, retry) {
+                map.entry(c.path.clone()).or_default().push(c);
+            }
+        }
+
+        let mut ret: Vec<_> = map.into_iter().collect();
+        ret.sort_unstable_by(|x, y| x.0.cmp(&y.0));
+        ret
+    }
+
+    fn is_retried(checked: &Checked, retry: Option<&HashMap<PathBuf, Fixed>>) -> bool {
+        let retry = match retry {
+            Some(x) => x,
+            None => return true,
+        };
+        let fixed = match retry.get(&checked.path) {
+            Some(x) => x,
+            None => return false,
+        };
+
+        let rewritten = fixed.rewritten.iter().any(|(rule, beg, end)| {
+            *rule == checked.rule && checked.beg < *end && *beg < checked.end
+        });
+        fixed.conflicted.contains(&checked.rule) && !rewritten
+    }
+
+    fn apply(src: &str, fixes: &[&Checked]) -> (String, Fixed) {
+        let mut fixes = fixes.to_vec();
+        fixes.sort_by_key(|x| (x.beg, x.end));
+
+        let mut fixed = Fixed::default();
+        let mut dst = String::new();
+        let mut last: Option<&Checked> = None;
+        for fix in fixes {
+            let replace = fix.replace.as_deref().unwrap_or("");
+
+            // Fixes which overlap with the previous one are not applied.
+            // The same fix from different rules is applied only once.
+            if let Some(last) = last {
+                let same = last.beg == fix.beg
+                    && last.end == fix.end
+                    && last.replace.as_deref() == Some(replace);
+                if same {
+                    continue;
+                }
+                if fix.beg < last.end || fix.beg == last.beg {
+                    fixed.conflicted.push(fix.rule.clone());
+                    continue;
+                }
+            }
+
+            let pos = last.map(|x| x.end).unwrap_or(0);
+            dst.push_str(&src[pos..fix.beg]);
+            let beg = dst.len();
+            dst.push_str(replace);
+            fixed.applied += 1;
+            fixed.rewritten.push((fix.rule.clone(), beg, dst.len()));
+            last = Some(fix);
+        }
+
+        let pos = last.map(|x| x.end).unwrap_or(0);
+        dst.push_str(&src[pos..]);
+
+        (dst, fixed)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// Test
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lint::Severity;
+
+    fn gen_fix(beg: usize, end: usize, name: &str, replace: &str) -> Checked {
+        Checked {
+            path: PathBuf::from("aaa"),
+            beg,
+            end,
+            state: CheckedState::Fail,
+            severity: Severity::Error,
+            rule: String::from(name),
+            name: String::from(name),
+            hint: String::from(""),
+            reason: None,
+            replace: Some(String::from(replace)),
+        }
+    }
+
+    #[test]
+    fn test_apply() {
+        let src = "always @ begin\nalways @ begin\n";
+        let fixes = [
+            gen_fix(15, 21, "aaa", "always_ff"),
+            gen_fix(0, 6, "aaa", "always_ff"),
+            gen_fix(0, 6, "bbb", "always_ff"),
+            gen_fix(2, 8, "ccc", "xxx"),
+        ];
+        let fixes: Vec<_> = fixes.iter().collect();
+
+        let (dst, fixed) = Fixer::apply(src, &fixes);
+        assert_eq!(dst, "always_ff @ begin\nalways_ff @ begin\n");
+        assert_eq!(
+            fixed,
+            Fixed {
+                applied: 2,
+                conflicted: vec![String::from("ccc")],
+                rewritten: vec![(String::from("aaa"), 0, 9), (String::from("aaa"), 18, 27)],
+            }
+        );
+    }
+
+    #[test]
+    fn test_gen_fixed() {
+        let mut sources = Sources::new();
+        sources.insert(PathBuf::from("aaa"), String::from("always @ begin\n"));
+        let checked = [gen_fix(0, 6, "aaa", "always_ff")];
+
+        let fixed = Fixer::gen_fixed(&checked, &sources, None).unwrap();
+        assert_eq!(fixed.len(), 1);
+        assert_eq!(fixed[0].1, "always @ begin\n");
+        assert_eq!(fixed[0].2, "always_ff @ begin\n");
+        assert!(Fixer::gen_fixed(&checked, &Sources::new(), None).is_err());
+
+        let mut retry = HashMap::new();
+        retry.insert(PathBuf::from("aaa"), Fixed::default());
+        let fixed = Fixer::gen_fixed(&checked, &sources, Some(&retry)).unwrap();
+        assert!(fixed.is_empty());
+    }
+
+    #[test]
+    fn test_run() {
+        let rule: RuleSet = toml::from_str(
+            r#"
+[[rules]]
+name      = "aaa"
+pattern   = 'wire\s+'
+forbidden = 'wire\s+'
+hint      = ""
+replace   = "logic "
+includes  = ["*.sv"]
+
+[[rules]]
+name      = "bbb"
+pattern   = '(\w+)\s+clk\b'
+forbidden = '(\w+)\s+clk\b'
+hint      = ""
+replace   = "${1} i_clk"
+includes  = ["*.sv"]
+            "#,
+        )
+        .unwrap();
+
+        let path = PathBuf::from("ccc.sv");
+        let (checked, sources) = rule
+            .check_source(&path, String::from("wire clk;\n"), false)
+            .unwrap();
+        let (checked, fixed) = Fixer::run(&rule, checked, sources.clone(), false).unwrap();
+        assert_eq!(fixed[&path], "logic i_clk;\n");
+        assert!(checked.iter().all(|x| x.state != CheckedState::Fail));
+        assert_eq!(
+            Fixer::diff(&sources, &fixed),
+            "--- a/ccc.sv\n+++ b/ccc.sv\n@@ -1 +1 @@\n-wire clk;\n+logic i_clk;\n"
+        );
+    }
+
+    #[test]
+    fn test_run_with_self_match() {
+        let rule: RuleSet = toml::from_str(
+            r#"
+[[rules]]
+name      = "aaa"
+pattern   = 'reg\b'
+forbidden = 'reg\b'
+hint      = ""
+replace   = "reg logic"
+includes  = ["*.sv"]
+
+[[rules]]
+name      = "bbb"
+pattern   = 'reg x'
+forbidden = 'reg x'
+hint      = ""
+replace   = "logic x"
+includes  = ["*.sv"]
+            "#,
+        )
+        .unwrap();
+
+        let path = PathBuf::from("ccc.sv");
+        let (checked, sources) = rule
+            .check_source(&path, String::from("reg x;\n"), false)
+            .unwrap();
+        let (checked, fixed) = Fixer::run(&rule, checked, sources, false).unwrap();
+        assert_eq!(fixed[&path], "reg logic x;\n");
+        assert!(checked
+            .iter()
+            .any(|x| x.state == CheckedState::Fail && x.rule == "aaa"));
+    }
+
+    #[test]
+    fn test_gen_diff() {
+        let src = "aaa\nalways @ begin\nbbb\n";
+        let dst = "aaa\nalways_ff @ begin\nbbb\n";
+
+        let diff = Fixer::gen_diff(&PathBuf::from("ccc"), src, dst);
+        assert_eq!(
+            diff,
+            "--- a/ccc\n+++ b/ccc\n@@ -1,3 +1,3 @@\n aaa\n-always @ begin\n+always_ff @ begin\n bbb\n"
+        );
+        assert_eq!(Fixer::gen_diff(&PathBuf::from("ccc"), src, src), "");
+    }
+}
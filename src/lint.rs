@@ -1,29 +1,212 @@
-use anyhow::{Context, Error};
-use glob::glob;
-use regex::Regex;
-use serde::Deserialize;
+use crate::walker::Walker;
+use anyhow::{format_err, Context, Error};
+use glob::{MatchOptions, Pattern};
+use rayon::prelude::*;
+use regex::{Captures, Match, Regex};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
 use std::fs::File;
 use std::io::Read;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
 
 // -------------------------------------------------------------------------------------------------
 // RuleSet
 // -------------------------------------------------------------------------------------------------
 
+/// Source text of checked files
+pub type Sources = HashMap<PathBuf, String>;
+
 #[derive(Deserialize, Debug)]
 pub struct RuleSet {
     pub rules: Vec<Rule>,
+
+    /// Base directory of globs. Globs are relative to the current directory if empty.
+    #[serde(skip)]
+    pub root: PathBuf,
+
+    /// Don't respect ignore files and don't skip build/VCS directories
+    #[serde(skip)]
+    pub no_ignore: bool,
 }
 
 impl RuleSet {
     #[cfg_attr(tarpaulin, skip)]
-    pub fn check(&self) -> Result<Vec<Checked>, Error> {
+    pub fn check(
+        &self,
+        paths: &[PathBuf],
+        report_unused: bool,
+    ) -> Result<(Vec<Checked>, Sources), Error> {
+        let (entries, explicit): (Vec<_>, Vec<_>) = self.gen_entries(paths)?.into_iter().unzip();
+
+        let matched = self
+            .rules
+            .par_iter()
+            .map(|rule| rule.match_entries(&entries, &explicit, &self.root, self.no_ignore))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let mut targets = vec![Vec::new(); entries.len()];
+        for (rule, matched) in self.rules.iter().zip(matched) {
+            for (target, matched) in targets.iter_mut().zip(matched) {
+                if matched {
+                    target.push(rule);
+                }
+            }
+        }
+
+        // Each file is read only once and checked by all the target rules.
+        // The order of results is kept the same as 'entries' to be deterministic.
+        let results = entries
+            .into_par_iter()
+            .zip(targets)
+            .filter(|(_, rules)| !rules.is_empty())
+            .map(|(entry, rules)| {
+                let mut f = File::open(&entry)
+                    .with_context(|| format!("failed to open: '{}'", entry.to_string_lossy()))?;
+                let mut s = String::new();
+                let _ = f.read_to_string(&mut s);
+
+                let checked = RuleSet::check_rules(&rules, &entry, &s, report_unused);
+                Ok((entry, s, checked))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let mut checked = Vec::new();
+        let mut sources = Sources::new();
+        for (entry, s, mut x) in results {
+            checked.append(&mut x);
+            sources.insert(entry, s);
+        }
+        Ok((checked, sources))
+    }
+
+    /// Returns files to be checked with whether each file is given explicitly
+    #[cfg_attr(tarpaulin, skip)]
+    fn gen_entries(&self, paths: &[PathBuf]) -> Result<Vec<(PathBuf, bool)>, Error> {
+        let mut walker = Walker::new(self.no_ignore);
+        walker.hidden = self
+            .rules
+            .iter()
+            .any(|rule| rule.includes.iter().any(|x| Rule::is_hidden_glob(x)));
+        let current = env::current_dir()?;
+
         let mut ret = Vec::new();
-        for rule in &self.rules {
-            ret.append(&mut rule.check()?);
+        if paths.is_empty() {
+            // Only the literal prefixes of includes are walked
+            let mut bases = Vec::new();
+            for rule in &self.rules {
+                for (base, _) in Rule::gen_globs(&rule.includes, &self.root)? {
+                    bases.push(base);
+                }
+            }
+            bases.sort();
+            bases.dedup();
+
+            let mut walked: Vec<PathBuf> = Vec::new();
+            for base in bases {
+                if walked.iter().any(|x| base.starts_with(x)) {
+                    continue;
+                }
+                if base.is_dir() {
+                    for entry in walker.walk(&base)? {
+                        ret.push((relative_path(&entry, &current), false));
+                    }
+                    walked.push(base);
+                } else if base.is_file() {
+                    ret.push((relative_path(&base, &current), false));
+                }
+            }
+        } else {
+            for path in paths {
+                if path.is_dir() {
+                    for entry in walker.walk(&normalize_path(&current.join(path)))? {
+                        ret.push((relative_path(&entry, &current), false));
+                    }
+                } else if path.is_file() {
+                    // The same form as walked entries to dedup absolute and relative paths
+                    let path = relative_path(&normalize_path(&current.join(path)), &current);
+                    ret.push((path, true));
+                } else {
+                    return Err(format_err!("file not found: '{}'", path.to_string_lossy()));
+                }
+            }
         }
+        // Explicit one is kept if the same file is given and walked
+        ret.sort_by(|x, y| x.0.cmp(&y.0).then(y.1.cmp(&x.1)));
+        ret.dedup_by(|x, y| x.0 == y.0);
         Ok(ret)
     }
+
+    pub fn check_source(
+        &self,
+        path: &Path,
+        src: String,
+        report_unused: bool,
+    ) -> Result<(Vec<Checked>, Sources), Error> {
+        let path = normalize_path(path);
+
+        let mut rules = Vec::new();
+        for rule in &self.rules {
+            if rule.is_target(&path, &self.root, self.no_ignore)? {
+                rules.push(rule);
+            }
+        }
+
+        let checked = RuleSet::check_rules(&rules, &path, &src, report_unused);
+        let mut sources = Sources::new();
+        sources.insert(path, src);
+        Ok((checked, sources))
+    }
+
+    /// Rejects the fields which are ignored by the mode of each rule
+    pub fn validate(&self) -> Result<(), Error> {
+        for rule in &self.rules {
+            let mode = match rule.mode {
+                Mode::Check => "check",
+                Mode::Forbid => "forbid",
+                Mode::Require => "require",
+            };
+            if rule.mode != Mode::Check && (rule.required.is_some() || rule.forbidden.is_some()) {
+                return Err(format_err!(
+                    "'required' and 'forbidden' can't be used with mode = \"{}\": '{}'",
+                    mode,
+                    rule.name
+                ));
+            }
+            if rule.mode != Mode::Require && rule.lines.is_some() {
+                return Err(format_err!(
+                    "'lines' can't be used with mode = \"{}\": '{}'",
+                    mode,
+                    rule.name
+                ));
+            }
+            if let (Some(min), Some(max)) = (rule.min_count, rule.max_count) {
+                if min > max {
+                    return Err(format_err!(
+                        "'min_count' must not exceed 'max_count': '{}'",
+                        rule.name
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_rules(rules: &[&Rule], path: &Path, src: &str, report_unused: bool) -> Vec<Checked> {
+        let mut ret = Vec::new();
+        for rule in rules {
+            ret.append(&mut rule.check_source(path, src));
+        }
+
+        let mut suppressions = Suppression::parse(src);
+        Suppression::apply(&mut ret, &mut suppressions);
+        if report_unused {
+            ret.append(&mut Suppression::gen_unused(path, &suppressions));
+        }
+        ret
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -37,17 +220,35 @@ pub struct Rule {
     #[serde(with = "serde_regex")]
     pub pattern: Regex,
 
-    #[serde(with = "serde_option_regex", default)]
-    pub required: Option<Regex>,
+    #[serde(with = "serde_option_capture_regex", default)]
+    pub required: Option<CaptureRegex>,
 
-    #[serde(with = "serde_option_regex", default)]
-    pub forbidden: Option<Regex>,
+    #[serde(with = "serde_option_capture_regex", default)]
+    pub forbidden: Option<CaptureRegex>,
 
     #[serde(with = "serde_option_regex", default)]
     pub ignore: Option<Regex>,
 
     pub hint: String,
 
+    #[serde(default)]
+    pub replace: Option<String>,
+
+    #[serde(default)]
+    pub severity: Severity,
+
+    #[serde(default)]
+    pub mode: Mode,
+
+    #[serde(default)]
+    pub lines: Option<usize>,
+
+    #[serde(default)]
+    pub min_count: Option<usize>,
+
+    #[serde(default)]
+    pub max_count: Option<usize>,
+
     pub includes: Vec<String>,
 
     #[serde(default)]
@@ -82,48 +283,129 @@ mod serde_option_regex {
     }
 }
 
+mod serde_option_capture_regex {
+    use super::CaptureRegex;
+    use serde::{self, Deserialize, Deserializer};
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<CaptureRegex>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        let r = CaptureRegex::new(&s).map_err(serde::de::Error::custom)?;
+        Ok(Some(r))
+    }
+}
+
 impl Rule {
-    #[cfg_attr(tarpaulin, skip)]
-    pub fn check(&self) -> Result<Vec<Checked>, Error> {
-        let mut ret = Vec::new();
-        let excludes = self.gen_excludes()?;
-        for g in &self.includes {
-            for entry in glob(&g).with_context(|| format!("failed to parse glob: '{}'", g))? {
-                let entry = entry?;
+    pub fn check_source(&self, path: &Path, src: &str) -> Vec<Checked> {
+        let ignore = self.gen_ignore(src);
+        self.gen_checked(path, src, &ignore)
+    }
 
-                if excludes.contains(&entry) {
-                    continue;
-                }
+    /// `path` is regarded as given explicitly
+    pub fn is_target(&self, path: &Path, root: &Path, no_ignore: bool) -> Result<bool, Error> {
+        Ok(self.match_entries(&[path.to_path_buf()], &[true], root, no_ignore)?[0])
+    }
 
-                let mut f = File::open(&entry)
-                    .with_context(|| format!("failed to open: '{}'", entry.to_string_lossy()))?;
-                let mut s = String::new();
-                let _ = f.read_to_string(&mut s);
+    fn match_entries(
+        &self,
+        entries: &[PathBuf],
+        explicit: &[bool],
+        root: &Path,
+        no_ignore: bool,
+    ) -> Result<Vec<bool>, Error> {
+        let includes = Rule::gen_globs(&self.includes, root)?;
+        let excludes = Rule::gen_globs(&self.excludes, root)?;
 
-                let ignore = self.gen_ignore(&s);
-                let mut checked = self.gen_checked(&entry, &s, &ignore);
+        let current = env::current_dir()?;
+        let mut ret = Vec::new();
+        for (entry, explicit) in entries.iter().zip(explicit) {
+            // Hidden files are matched by explicit '.' only unless given explicitly
+            let options = MatchOptions {
+                case_sensitive: true,
+                require_literal_separator: false,
+                require_literal_leading_dot: !no_ignore && !explicit,
+            };
 
-                ret.append(&mut checked);
-            }
+            let key = normalize_path(&current.join(entry));
+            let included = includes
+                .iter()
+                .any(|(_, x)| x.matches_path_with(&key, options));
+            let excluded = excludes
+                .iter()
+                .any(|(_, x)| x.matches_path_with(&key, options));
+            ret.push(included && !excluded);
         }
         Ok(ret)
     }
 
-    #[cfg_attr(tarpaulin, skip)]
-    fn gen_excludes(&self) -> Result<Vec<PathBuf>, Error> {
+    /// Resolves globs relative to `root` (or the current directory if empty) into absolute patterns.
+    /// The literal directory before the first wildcard is returned together to be walked.
+    fn gen_globs(globs: &[String], root: &Path) -> Result<Vec<(PathBuf, Pattern)>, Error> {
+        let root = if root.as_os_str().is_empty() {
+            env::current_dir()?
+        } else {
+            root.to_path_buf()
+        };
+
         let mut ret = Vec::new();
-        for g in &self.excludes {
-            for entry in glob(&g).with_context(|| format!("failed to parse glob: '{}'", g))? {
-                ret.push(entry?);
+        for g in globs {
+            let mut base = root.clone();
+            let mut wildcards = Vec::new();
+            for (i, x) in g.split('/').enumerate() {
+                if !wildcards.is_empty() || x.contains(['*', '?', '[']) {
+                    wildcards.push(x);
+                } else if i == 0 && x.is_empty() {
+                    base = PathBuf::from("/");
+                } else if !x.is_empty() {
+                    base.push(x);
+                }
             }
+            let base = normalize_path(&base);
+
+            let escaped = Pattern::escape(&base.to_string_lossy());
+            let pattern = if wildcards.is_empty() {
+                escaped
+            } else {
+                format!("{}/{}", escaped.trim_end_matches('/'), wildcards.join("/"))
+            };
+            let pattern =
+                Pattern::new(&pattern).with_context(|| format!("failed to parse glob: '{}'", g))?;
+            ret.push((base, pattern));
         }
         Ok(ret)
     }
 
+    /// Returns whether the glob can match hidden entries under its wildcards (e.g. `**/.hid/*.v`)
+    fn is_hidden_glob(glob: &str) -> bool {
+        glob.split('/')
+            .skip_while(|x| !x.contains(['*', '?', '[']))
+            .any(|x| x.starts_with('.') && x != "." && x != "..")
+    }
+
+    /// Replaces `${name}`/`${1}` by the captures, and `${file}`/`${match}` by the path and the matched text
+    fn interpolate(template: &str, path: &Path, cap: Option<&Captures>) -> String {
+        CaptureRegex::reference()
+            .replace_all(template, |x: &Captures| {
+                let m = cap.and_then(|cap| match x[1].parse::<usize>() {
+                    Ok(i) => cap.get(i),
+                    Err(_) => cap.name(&x[1]),
+                });
+                match (m, &x[1]) {
+                    (Some(m), _) => String::from(m.as_str()),
+                    (None, "file") => path.to_string_lossy().into_owned(),
+                    (None, "match") => cap.map(|x| String::from(&x[0])).unwrap_or_default(),
+                    (None, _) => String::new(),
+                }
+            })
+            .into_owned()
+    }
+
     fn gen_ignore(&self, src: &str) -> Vec<(usize, usize)> {
         let mut ret = Vec::new();
         if let Some(ref ignore) = self.ignore {
-            for m in ignore.find_iter(&src) {
+            for m in ignore.find_iter(src) {
                 ret.push((m.start(), m.end()));
             }
         }
@@ -131,86 +413,540 @@ impl Rule {
     }
 
     fn gen_checked(&self, entry: &Path, src: &str, ignore: &[(usize, usize)]) -> Vec<Checked> {
+        let mut ret = if self.mode == Mode::Require {
+            vec![self.gen_checked_file(entry, src, ignore)]
+        } else {
+            self.gen_checked_points(entry, src, ignore)
+        };
+        ret.extend(self.gen_checked_count(entry, src, ignore));
+
+        if ret.is_empty() {
+            let checked = Checked {
+                path: entry.to_path_buf(),
+                beg: 0,
+                end: 0,
+                state: CheckedState::Unmatch,
+                severity: self.severity,
+                rule: self.name.clone(),
+                name: String::from(""),
+                hint: String::from(""),
+                reason: None,
+                replace: None,
+            };
+            ret.push(checked);
+        }
+
+        ret
+    }
+
+    fn gen_checked_points(
+        &self,
+        entry: &Path,
+        src: &str,
+        ignore: &[(usize, usize)],
+    ) -> Vec<Checked> {
         let mut ret = Vec::new();
-        for m in self.pattern.find_iter(&src) {
+        for cap in self.pattern.captures_iter(src) {
+            let m = cap.get(0).unwrap();
             let pat_start = m.start();
             let pat_end = m.end();
             let mut pass = true;
-            let mut skip = false;
+            let mut reason = None;
 
             for (beg, end) in ignore {
                 if *beg <= pat_start && pat_start < *end {
-                    skip = true;
+                    reason = Some("matched 'ignore' pattern");
                 }
             }
 
-            if !skip {
-                if let Some(ref required) = self.required {
-                    pass &= match required.find_at(&src, pat_start) {
-                        Some(x) => x.start() == pat_start,
-                        None => false,
-                    };
-                }
+            if reason.is_none() {
+                match self.mode {
+                    Mode::Forbid | Mode::Require => pass = false,
+                    Mode::Check
+                        if self.required.is_none()
+                            && self.forbidden.is_none()
+                            && self.min_count.is_none()
+                            && self.max_count.is_none() =>
+                    {
+                        reason = Some("neither 'required' nor 'forbidden' is defined");
+                    }
+                    Mode::Check => {
+                        if let Some(ref required) = self.required {
+                            pass &= match required.find_at(src, pat_start, &cap) {
+                                Some(x) => x.start() == pat_start,
+                                None => false,
+                            };
+                        }
 
-                if let Some(ref forbidden) = self.forbidden {
-                    pass &= match forbidden.find_at(&src, pat_start) {
-                        Some(x) => x.start() != pat_start,
-                        None => true,
-                    };
+                        if let Some(ref forbidden) = self.forbidden {
+                            pass &= match forbidden.find_at(src, pat_start, &cap) {
+                                Some(x) => x.start() != pat_start,
+                                None => true,
+                            };
+                        }
+                    }
                 }
             }
 
-            let state = if skip {
+            let state = if reason.is_some() {
                 CheckedState::Skip
             } else if pass {
                 CheckedState::Pass
             } else {
                 CheckedState::Fail
             };
+            let reason = reason.map(String::from);
+
+            let replace = match self.replace {
+                Some(ref x) if state == CheckedState::Fail => {
+                    let mut dst = String::new();
+                    cap.expand(x, &mut dst);
+                    Some(dst)
+                }
+                _ => None,
+            };
 
             let checked = Checked {
                 path: entry.to_path_buf(),
                 beg: pat_start,
                 end: pat_end,
                 state,
-                name: self.name.clone(),
-                hint: self.hint.clone(),
+                severity: self.severity,
+                rule: self.name.clone(),
+                name: Rule::interpolate(&self.name, entry, Some(&cap)),
+                hint: Rule::interpolate(&self.hint, entry, Some(&cap)),
+                reason,
+                replace,
             };
 
             ret.push(checked);
         }
 
-        if ret.is_empty() {
-            let checked = Checked {
-                path: entry.to_path_buf(),
-                beg: 0,
-                end: 0,
-                state: CheckedState::Unmatch,
-                name: String::from(""),
-                hint: String::from(""),
+        ret
+    }
+
+    /// Checks that `pattern` is contained in the file, or the first `lines` lines
+    fn gen_checked_file(&self, entry: &Path, src: &str, ignore: &[(usize, usize)]) -> Checked {
+        let limit = match self.lines {
+            Some(0) => 0,
+            Some(n) => src
+                .match_indices('\n')
+                .nth(n - 1)
+                .map_or(src.len(), |(i, _)| i + 1),
+            None => src.len(),
+        };
+
+        let cap = self.pattern.captures_iter(&src[..limit]).find(|cap| {
+            let pos = cap.get(0).unwrap().start();
+            !ignore.iter().any(|(beg, end)| *beg <= pos && pos < *end)
+        });
+
+        // The failure is located at the file start because there is no matched point
+        let (beg, end, state) = match cap {
+            Some(ref cap) => {
+                let m = cap.get(0).unwrap();
+                (m.start(), m.end(), CheckedState::Pass)
+            }
+            None => (0, 0, CheckedState::Fail),
+        };
+
+        Checked {
+            path: entry.to_path_buf(),
+            beg,
+            end,
+            state,
+            severity: self.severity,
+            rule: self.name.clone(),
+            name: Rule::interpolate(&self.name, entry, cap.as_ref()),
+            hint: Rule::interpolate(&self.hint, entry, cap.as_ref()),
+            reason: None,
+            replace: None,
+        }
+    }
+
+    /// Checks that the number of `pattern` matches in the file is between `min_count` and `max_count`
+    fn gen_checked_count(
+        &self,
+        entry: &Path,
+        src: &str,
+        ignore: &[(usize, usize)],
+    ) -> Option<Checked> {
+        if self.min_count.is_none() && self.max_count.is_none() {
+            return None;
+        }
+
+        let caps: Vec<_> = self
+            .pattern
+            .captures_iter(src)
+            .filter(|cap| {
+                let pos = cap.get(0).unwrap().start();
+                !ignore.iter().any(|(beg, end)| *beg <= pos && pos < *end)
+            })
+            .collect();
+
+        let mut lines = Vec::new();
+        let mut line = 1;
+        let mut last = 0;
+        for cap in &caps {
+            let pos = cap.get(0).unwrap().start();
+            line += src[last..pos].matches('\n').count();
+            last = pos;
+            lines.push(line.to_string());
+        }
+        let found = if caps.is_empty() {
+            String::from("found no occurrence")
+        } else {
+            format!(
+                "found {} occurrence(s) at line {}",
+                caps.len(),
+                lines.join(", ")
+            )
+        };
+
+        // Too many occurrences are located at the first excess one, and too few at the file start
+        let (cap, reason) = match (self.min_count, self.max_count) {
+            (Some(min), _) if caps.len() < min => {
+                (None, format!("{}, at least {} required", found, min))
+            }
+            (_, Some(max)) if caps.len() > max => (
+                Some(&caps[max]),
+                format!("{}, at most {} allowed", found, max),
+            ),
+            _ => return None,
+        };
+        let (beg, end) = match cap {
+            Some(cap) => {
+                let m = cap.get(0).unwrap();
+                (m.start(), m.end())
+            }
+            None => (0, 0),
+        };
+
+        Some(Checked {
+            path: entry.to_path_buf(),
+            beg,
+            end,
+            state: CheckedState::Fail,
+            severity: self.severity,
+            rule: self.name.clone(),
+            name: Rule::interpolate(&self.name, entry, cap),
+            hint: Rule::interpolate(&self.hint, entry, cap),
+            reason: Some(reason),
+            replace: None,
+        })
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// CaptureRegex
+// -------------------------------------------------------------------------------------------------
+
+/// Regex which may refer to the captures of `pattern` by `${name}` or `${1}`
+#[derive(Debug)]
+pub enum CaptureRegex {
+    Fixed(Regex),
+    Template(String),
+}
+
+impl CaptureRegex {
+    fn reference() -> &'static Regex {
+        static REFERENCE: OnceLock<Regex> = OnceLock::new();
+        REFERENCE.get_or_init(|| Regex::new(r"\$\{(\w+)\}").unwrap())
+    }
+
+    pub fn new(s: &str) -> Result<CaptureRegex, regex::Error> {
+        let reference = CaptureRegex::reference();
+        if reference.is_match(s) {
+            // Check the syntax in advance because the template is compiled at each match.
+            // References are replaced by a literal because they may be followed by a repetition.
+            Regex::new(&reference.replace_all(s, "x"))?;
+            Ok(CaptureRegex::Template(String::from(s)))
+        } else {
+            Ok(CaptureRegex::Fixed(Regex::new(s)?))
+        }
+    }
+
+    fn find_at<'t>(&self, src: &'t str, pos: usize, cap: &Captures) -> Option<Match<'t>> {
+        match self {
+            CaptureRegex::Fixed(x) => x.find_at(src, pos),
+            CaptureRegex::Template(x) => {
+                let x = CaptureRegex::expand(x, cap);
+                Regex::new(&x).ok()?.find_at(src, pos)
+            }
+        }
+    }
+
+    /// Replaces references by the escaped captured text, or empty for unmatched groups
+    fn expand(template: &str, cap: &Captures) -> String {
+        CaptureRegex::reference()
+            .replace_all(template, |x: &Captures| {
+                let m = match x[1].parse::<usize>() {
+                    Ok(i) => cap.get(i),
+                    Err(_) => cap.name(&x[1]),
+                };
+                m.map(|m| regex::escape(m.as_str())).unwrap_or_default()
+            })
+            .into_owned()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// Path
+// -------------------------------------------------------------------------------------------------
+
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut ret = PathBuf::new();
+    for x in path.components() {
+        match x {
+            Component::CurDir => (),
+            Component::ParentDir if ret.file_name().is_some() => {
+                ret.pop();
+            }
+            _ => ret.push(x),
+        }
+    }
+    ret
+}
+
+pub fn relative_path(path: &Path, base: &Path) -> PathBuf {
+    let path: Vec<_> = path.components().collect();
+    let base: Vec<_> = base.components().collect();
+    let common = path.iter().zip(&base).take_while(|(x, y)| x == y).count();
+
+    let mut ret = PathBuf::new();
+    for _ in common..base.len() {
+        ret.push("..");
+    }
+    for x in &path[common..] {
+        ret.push(x);
+    }
+    ret
+}
+
+// -------------------------------------------------------------------------------------------------
+// Suppression
+// -------------------------------------------------------------------------------------------------
+
+#[derive(Debug, PartialEq)]
+enum SuppressionKind {
+    NextLine,
+    Range,
+    File,
+}
+
+#[derive(Debug)]
+struct Suppression {
+    kind: SuppressionKind,
+    name: Option<String>,
+    beg: usize,
+    end: usize,
+    range: (usize, usize),
+    used: bool,
+}
+
+impl Suppression {
+    fn pattern() -> &'static Regex {
+        static DIRECTIVE: OnceLock<Regex> = OnceLock::new();
+        DIRECTIVE.get_or_init(|| {
+            Regex::new(r"flexlint-(disable-next-line|disable-file|disable|enable)\b([^\r\n]*)")
+                .unwrap()
+        })
+    }
+
+    fn parse(src: &str) -> Vec<Suppression> {
+        let directive = Suppression::pattern();
+
+        let mut ret = Vec::new();
+        let mut opened: Vec<Suppression> = Vec::new();
+        for cap in directive.captures_iter(src) {
+            let m = cap.get(0).unwrap();
+            let name = cap[2]
+                .trim()
+                .trim_end_matches("*/")
+                .trim_end_matches("-->")
+                .trim();
+            let name = if name.is_empty() {
+                None
+            } else {
+                Some(String::from(name))
             };
-            ret.push(checked);
+
+            let mut suppression = Suppression {
+                kind: SuppressionKind::File,
+                name,
+                beg: m.start(),
+                end: m.end(),
+                range: (0, src.len()),
+                used: false,
+            };
+
+            match &cap[1] {
+                "disable-next-line" => {
+                    let next_beg = match src[m.end()..].find('\n') {
+                        Some(x) => m.end() + x + 1,
+                        None => src.len(),
+                    };
+                    let next_end = match src[next_beg..].find('\n') {
+                        Some(x) => next_beg + x,
+                        None => src.len(),
+                    };
+                    suppression.kind = SuppressionKind::NextLine;
+                    suppression.range = (next_beg, next_end);
+                    ret.push(suppression);
+                }
+                "disable-file" => {
+                    ret.push(suppression);
+                }
+                "disable" => {
+                    suppression.kind = SuppressionKind::Range;
+                    suppression.range = (m.end(), src.len());
+                    opened.push(suppression);
+                }
+                _ => {
+                    let (mut closed, rest): (Vec<_>, Vec<_>) = opened
+                        .into_iter()
+                        .partition(|x| suppression.name.is_none() || x.name == suppression.name);
+                    for x in &mut closed {
+                        x.range.1 = m.start();
+                    }
+                    ret.append(&mut closed);
+                    opened = rest;
+                }
+            }
         }
+        ret.append(&mut opened);
+        ret.sort_by_key(|x| x.beg);
+        ret
+    }
 
+    fn apply(checked: &mut [Checked], suppressions: &mut [Suppression]) {
+        for c in checked {
+            if c.state != CheckedState::Pass && c.state != CheckedState::Fail {
+                continue;
+            }
+
+            for s in suppressions.iter_mut() {
+                if s.covers(c) {
+                    if c.state == CheckedState::Fail {
+                        s.used = true;
+                    }
+                    c.state = CheckedState::Skip;
+                    c.reason = Some(format!("suppressed by '{}'", s.directive()));
+                    c.replace = None;
+                    break;
+                }
+            }
+        }
+    }
+
+    fn gen_unused(path: &Path, suppressions: &[Suppression]) -> Vec<Checked> {
+        let mut ret = Vec::new();
+        for s in suppressions {
+            if s.used {
+                continue;
+            }
+
+            ret.push(Checked {
+                path: path.to_path_buf(),
+                beg: s.beg,
+                end: s.end,
+                state: CheckedState::Fail,
+                severity: Severity::Warning,
+                rule: String::from("unused suppression"),
+                name: String::from("unused suppression"),
+                hint: format!("'{}' doesn't suppress any failure", s.directive()),
+                reason: None,
+                replace: None,
+            });
+        }
         ret
     }
+
+    fn covers(&self, checked: &Checked) -> bool {
+        let name_match = match self.name {
+            Some(ref x) => *x == checked.rule,
+            None => true,
+        };
+        name_match && self.range.0 <= checked.beg && checked.beg < self.range.1
+    }
+
+    fn directive(&self) -> String {
+        let kind = match self.kind {
+            SuppressionKind::NextLine => "flexlint-disable-next-line",
+            SuppressionKind::Range => "flexlint-disable",
+            SuppressionKind::File => "flexlint-disable-file",
+        };
+        match self.name {
+            Some(ref x) => format!("{} {}", kind, x),
+            None => String::from(kind),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// Severity
+// -------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    #[default]
+    Error,
+}
+
+impl FromStr for Severity {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "info" => Ok(Severity::Info),
+            "warning" => Ok(Severity::Warning),
+            "error" => Ok(Severity::Error),
+            _ => Err(format_err!("unknown severity: '{}'", s)),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// Mode
+// -------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// Check by `required` and `forbidden`
+    #[default]
+    Check,
+    /// Every match of `pattern` is a failure
+    Forbid,
+    /// The file without any match of `pattern` is a failure
+    Require,
 }
 
 // -------------------------------------------------------------------------------------------------
 // Checked
 // -------------------------------------------------------------------------------------------------
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct Checked {
     pub path: PathBuf,
     pub beg: usize,
     pub end: usize,
     pub state: CheckedState,
+    pub severity: Severity,
+    pub rule: String,
     pub name: String,
     pub hint: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replace: Option<String>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum CheckedState {
     Pass,
     Fail,
@@ -225,9 +961,8 @@ pub enum CheckedState {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use toml;
 
-    static TOML_SAMPLE: &'static str = r#"
+    static TOML_SAMPLE: &str = r#"
 [[rules]]
 name      = "aaa"
 pattern   = 'bbb'
@@ -235,10 +970,12 @@ required  = 'ccc'
 forbidden = 'ddd'
 ignore    = 'eee'
 hint      = "fff"
+replace   = "hhh"
+severity  = "warning"
 includes  = ["ggg"]
         "#;
 
-    static C_RULE: &'static str = r#"
+    static C_RULE: &str = r#"
 [[rules]]
 name      = "'if' with brace"
 pattern   = '(?m)(^|[\t ])if\s'
@@ -248,7 +985,7 @@ hint      = "multiline 'if' must have brace"
 includes  = ["**/*.c", "**/*.cpp"]
         "#;
 
-    static C_SRC: &'static str = r#"
+    static C_SRC: &str = r#"
 int test() {
     int hoge = 0;
 
@@ -271,7 +1008,7 @@ int test() {
 }
         "#;
 
-    static VERILOG_RULE: &'static str = r#"
+    static VERILOG_RULE: &str = r#"
 [[rules]]
 name     = "'if' with 'begin'"
 pattern  = '(?m)(^|[\t ])if\s'
@@ -281,7 +1018,7 @@ hint     = "'if' statement must have 'begin'"
 includes = ["**/*.v", "**/*.sv"]
         "#;
 
-    static VERILOG_SRC: &'static str = r#"
+    static VERILOG_SRC: &str = r#"
 module test ();
 
     wire clk;
@@ -310,7 +1047,7 @@ endmodule
 
     #[test]
     fn test_deserialize_ruleset() {
-        let rule: RuleSet = toml::from_str(&TOML_SAMPLE).unwrap();
+        let rule: RuleSet = toml::from_str(TOML_SAMPLE).unwrap();
         assert_eq!(rule.rules[0].name, "aaa");
         assert_eq!(
             format!("{:?}", rule.rules[0].pattern),
@@ -318,24 +1055,26 @@ endmodule
         );
         assert_eq!(
             format!("{:?}", rule.rules[0].required),
-            format!("{:?}", Some(Regex::new("ccc").unwrap()))
+            format!("{:?}", Some(CaptureRegex::new("ccc").unwrap()))
         );
         assert_eq!(
             format!("{:?}", rule.rules[0].forbidden),
-            format!("{:?}", Some(Regex::new("ddd").unwrap()))
+            format!("{:?}", Some(CaptureRegex::new("ddd").unwrap()))
         );
         assert_eq!(
             format!("{:?}", rule.rules[0].ignore),
             format!("{:?}", Some(Regex::new("eee").unwrap()))
         );
         assert_eq!(rule.rules[0].hint, "fff");
+        assert_eq!(rule.rules[0].replace.as_deref(), Some("hhh"));
+        assert_eq!(rule.rules[0].severity, Severity::Warning);
         assert_eq!(rule.rules[0].includes[0], "ggg");
     }
 
     #[test]
     fn test_gen_ignore() {
-        let rule: RuleSet = toml::from_str(&C_RULE).unwrap();
-        let ignore = rule.rules[0].gen_ignore(&C_SRC);
+        let rule: RuleSet = toml::from_str(C_RULE).unwrap();
+        let ignore = rule.rules[0].gen_ignore(C_SRC);
         assert_eq!(ignore.len(), 3);
         assert_eq!(ignore[0], (142, 157));
         assert_eq!(ignore[1], (161, 178));
@@ -344,20 +1083,21 @@ endmodule
 
     #[test]
     fn test_gen_checked_with_required() {
-        let rule: RuleSet = toml::from_str(&VERILOG_RULE).unwrap();
-        let ignore = rule.rules[0].gen_ignore(&VERILOG_SRC);
-        let checked = rule.rules[0].gen_checked(&PathBuf::from(""), &VERILOG_SRC, &ignore);
+        let rule: RuleSet = toml::from_str(VERILOG_RULE).unwrap();
+        let ignore = rule.rules[0].gen_ignore(VERILOG_SRC);
+        let checked = rule.rules[0].gen_checked(&PathBuf::from(""), VERILOG_SRC, &ignore);
         assert_eq!(checked.len(), 1);
         assert_eq!(checked[0].state, CheckedState::Fail);
+        assert_eq!(checked[0].severity, Severity::Error);
         assert_eq!(checked[0].beg, 198);
         assert_eq!(checked[0].end, 202);
     }
 
     #[test]
     fn test_gen_checked_with_forbidden() {
-        let rule: RuleSet = toml::from_str(&C_RULE).unwrap();
-        let ignore = rule.rules[0].gen_ignore(&C_SRC);
-        let checked = rule.rules[0].gen_checked(&PathBuf::from(""), &C_SRC, &ignore);
+        let rule: RuleSet = toml::from_str(C_RULE).unwrap();
+        let ignore = rule.rules[0].gen_ignore(C_SRC);
+        let checked = rule.rules[0].gen_checked(&PathBuf::from(""), C_SRC, &ignore);
         assert_eq!(checked.len(), 5);
         assert_eq!(checked[0].state, CheckedState::Fail);
         assert_eq!(checked[0].beg, 36);
@@ -375,4 +1115,491 @@ endmodule
         assert_eq!(checked[4].beg, 189);
         assert_eq!(checked[4].end, 193);
     }
+
+    static SUPPRESSION_SRC: &str = r#"
+int test() {
+    // flexlint-disable-next-line 'if' with brace
+    if ( hoge )
+        return 1;
+
+    // flexlint-disable
+    if ( hoge )
+        return 1;
+    // flexlint-enable
+
+    if ( hoge )
+        return 1;
+
+    // flexlint-disable-next-line other rule
+    if ( hoge ) return 1;
+}
+        "#;
+
+    #[test]
+    fn test_suppression() {
+        let rule: RuleSet = toml::from_str(C_RULE).unwrap();
+        let ignore = rule.rules[0].gen_ignore(SUPPRESSION_SRC);
+        let mut checked = rule.rules[0].gen_checked(&PathBuf::from(""), SUPPRESSION_SRC, &ignore);
+
+        let mut suppressions = Suppression::parse(SUPPRESSION_SRC);
+        assert_eq!(suppressions.len(), 3);
+        assert_eq!(suppressions[0].kind, SuppressionKind::NextLine);
+        assert_eq!(suppressions[0].name.as_deref(), Some("'if' with brace"));
+        assert_eq!(suppressions[1].kind, SuppressionKind::Range);
+        assert_eq!(suppressions[1].name, None);
+
+        Suppression::apply(&mut checked, &mut suppressions);
+        assert_eq!(checked.len(), 4);
+        assert_eq!(checked[0].state, CheckedState::Skip);
+        assert_eq!(
+            checked[0].reason.as_deref(),
+            Some("suppressed by 'flexlint-disable-next-line 'if' with brace'")
+        );
+        assert_eq!(checked[1].state, CheckedState::Skip);
+        assert_eq!(checked[2].state, CheckedState::Fail);
+        assert_eq!(checked[3].state, CheckedState::Pass);
+
+        let unused = Suppression::gen_unused(&PathBuf::from(""), &suppressions);
+        assert_eq!(unused.len(), 1);
+        assert_eq!(unused[0].severity, Severity::Warning);
+        assert_eq!(unused[0].beg, suppressions[2].beg);
+    }
+
+    #[test]
+    fn test_gen_checked_with_replace() {
+        let rule: RuleSet = toml::from_str(
+            r#"
+[[rules]]
+name      = "verilog 'always' forbidden"
+pattern   = '(?m)(?P<head>^|[\t ])always(\s)'
+forbidden = '(?m)(^|[\t ])always\s'
+replace   = '${head}always_ff$2'
+hint      = "'always' must be replaced to 'always_comb'/'always_ff'"
+includes  = ["**/*.sv"]
+            "#,
+        )
+        .unwrap();
+        let checked = rule.rules[0].gen_checked(&PathBuf::from(""), VERILOG_SRC, &[]);
+        assert_eq!(checked.len(), 1);
+        assert_eq!(checked[0].state, CheckedState::Fail);
+        assert_eq!(checked[0].replace.as_deref(), Some(" always_ff "));
+    }
+
+    #[test]
+    fn test_match_entries() {
+        let rule: RuleSet = toml::from_str(
+            r#"
+[[rules]]
+name     = "aaa"
+pattern  = 'bbb'
+hint     = "ccc"
+includes = ["**/*.sv", "**/*.v"]
+excludes = ["external/*.sv"]
+            "#,
+        )
+        .unwrap();
+
+        let entries = vec![
+            PathBuf::from("./src/aaa.sv"),
+            PathBuf::from("bbb.v"),
+            PathBuf::from("ccc.c"),
+            PathBuf::from("external/ddd.sv"),
+            PathBuf::from(".hidden/eee.sv"),
+        ];
+        let matched = rule.rules[0]
+            .match_entries(&entries, &[false; 5], Path::new(""), false)
+            .unwrap();
+        assert_eq!(matched, vec![true, true, false, false, false]);
+
+        let matched = rule.rules[0]
+            .match_entries(&entries, &[false; 5], Path::new(""), true)
+            .unwrap();
+        assert_eq!(matched, vec![true, true, false, false, true]);
+
+        let matched = rule.rules[0]
+            .match_entries(&entries, &[true; 5], Path::new(""), false)
+            .unwrap();
+        assert_eq!(matched, vec![true, true, false, false, true]);
+    }
+
+    #[test]
+    fn test_check_source() {
+        let rule: RuleSet = toml::from_str(VERILOG_RULE).unwrap();
+
+        let (checked, sources) = rule
+            .check_source(
+                &PathBuf::from("./src/test.sv"),
+                String::from(VERILOG_SRC),
+                false,
+            )
+            .unwrap();
+        assert_eq!(sources[&PathBuf::from("src/test.sv")], VERILOG_SRC);
+        assert_eq!(checked.len(), 1);
+        assert_eq!(checked[0].path, PathBuf::from("src/test.sv"));
+        assert_eq!(checked[0].state, CheckedState::Fail);
+
+        let (checked, _) = rule
+            .check_source(
+                &PathBuf::from("src/test.c"),
+                String::from(VERILOG_SRC),
+                false,
+            )
+            .unwrap();
+        assert!(checked.is_empty());
+    }
+
+    #[test]
+    fn test_match_entries_with_root() {
+        let rule: RuleSet = toml::from_str(
+            r#"
+[[rules]]
+name     = "aaa"
+pattern  = 'bbb'
+hint     = "ccc"
+includes = ["src/*.sv"]
+            "#,
+        )
+        .unwrap();
+
+        let entries = vec![
+            PathBuf::from("/repo/src/aaa.sv"),
+            PathBuf::from("/repo/sub/../src/bbb.sv"),
+            PathBuf::from("/repo/sub/src/ccc.sv"),
+            PathBuf::from("/other/src/ddd.sv"),
+        ];
+        let matched = rule.rules[0]
+            .match_entries(&entries, &[false; 4], Path::new("/repo"), false)
+            .unwrap();
+        assert_eq!(matched, vec![true, true, false, false]);
+    }
+
+    #[test]
+    fn test_match_entries_with_relative_glob() {
+        let rule: RuleSet = toml::from_str(
+            r#"
+[[rules]]
+name     = "aaa"
+pattern  = 'bbb'
+hint     = "ccc"
+includes = ["./*.sv", "../src/*.sv", "/abs/dir/*.v"]
+            "#,
+        )
+        .unwrap();
+
+        let entries = vec![
+            PathBuf::from("/repo/sub/aaa.sv"),
+            PathBuf::from("/repo/src/bbb.sv"),
+            PathBuf::from("/abs/dir/ccc.v"),
+            PathBuf::from("/repo/other/ddd.sv"),
+            PathBuf::from("/repo/eee.sv"),
+        ];
+        let matched = rule.rules[0]
+            .match_entries(&entries, &[false; 5], Path::new("/repo/sub"), false)
+            .unwrap();
+        assert_eq!(matched, vec![true, true, true, false, false]);
+
+        let globs = Rule::gen_globs(&rule.rules[0].includes, Path::new("/repo/sub")).unwrap();
+        let bases: Vec<_> = globs.into_iter().map(|(x, _)| x).collect();
+        assert_eq!(
+            bases,
+            vec![
+                PathBuf::from("/repo/sub"),
+                PathBuf::from("/repo/src"),
+                PathBuf::from("/abs/dir")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_hidden_glob() {
+        assert!(Rule::is_hidden_glob("**/.hid/*.v"));
+        assert!(Rule::is_hidden_glob("src/*/.*.v"));
+        assert!(!Rule::is_hidden_glob(".hid/*.v"));
+        assert!(!Rule::is_hidden_glob("./**/*.v"));
+        assert!(!Rule::is_hidden_glob("**/../*.v"));
+    }
+
+    #[test]
+    fn test_path() {
+        assert_eq!(
+            normalize_path(Path::new("./aaa/../bbb/./ccc")),
+            PathBuf::from("bbb/ccc")
+        );
+        assert_eq!(normalize_path(Path::new("../aaa")), PathBuf::from("../aaa"));
+        assert_eq!(
+            relative_path(Path::new("/repo/src/aaa.sv"), Path::new("/repo/sub")),
+            PathBuf::from("../src/aaa.sv")
+        );
+        assert_eq!(
+            relative_path(Path::new("/repo/src/aaa.sv"), Path::new("/repo")),
+            PathBuf::from("src/aaa.sv")
+        );
+    }
+
+    static CAPTURE_RULE: &str = r#"
+[[rules]]
+name     = "endmodule label"
+pattern  = '(?s)module\s+(?P<name>\w+).*?endmodule'
+required = '(?s)module\s+\w+.*?endmodule\s*:\s*${name}\b'
+hint     = "endmodule label must match module name"
+includes = ["**/*.sv"]
+        "#;
+
+    static CAPTURE_SRC: &str = r#"
+module a_b;
+endmodule : a_b
+
+module ccc;
+endmodule : ddd
+
+module a.b;
+endmodule : a_b
+        "#;
+
+    #[test]
+    fn test_gen_checked_with_capture() {
+        let rule: RuleSet = toml::from_str(CAPTURE_RULE).unwrap();
+        let checked = rule.rules[0].gen_checked(&PathBuf::from(""), CAPTURE_SRC, &[]);
+        assert_eq!(checked.len(), 3);
+        assert_eq!(checked[0].state, CheckedState::Pass);
+        assert_eq!(checked[1].state, CheckedState::Fail);
+        assert_eq!(checked[2].state, CheckedState::Fail);
+
+        let src = "module.a";
+        let regex = CaptureRegex::new(r"${1}\.${2}${3}").unwrap();
+        let cap = Regex::new(r"(\w+)(\.)").unwrap().captures(src).unwrap();
+        assert_eq!(CaptureRegex::expand(r"${1}\.${2}${3}", &cap), r"module\.\.");
+        assert!(matches!(regex, CaptureRegex::Template(_)));
+        assert!(matches!(
+            CaptureRegex::new("a$"),
+            Ok(CaptureRegex::Fixed(_))
+        ));
+        assert!(CaptureRegex::new("(${name}").is_err());
+        assert!(CaptureRegex::new("${c}{2}").is_ok());
+        assert!(CaptureRegex::new("${name}+").is_ok());
+        assert!(CaptureRegex::new("^${name}*").is_ok());
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let src = "wire rst;";
+        let cap = Regex::new(r"wire\s+(?P<sig>\w+)(;)")
+            .unwrap()
+            .captures(src)
+            .unwrap();
+        let path = PathBuf::from("src/aaa.sv");
+
+        assert_eq!(
+            Rule::interpolate(
+                "signal `${sig}` must use the `_n` suffix",
+                &path,
+                Some(&cap)
+            ),
+            "signal `rst` must use the `_n` suffix"
+        );
+        assert_eq!(
+            Rule::interpolate("${file}: '${match}' ${2}${3}", &path, Some(&cap)),
+            "src/aaa.sv: 'wire rst;' ;"
+        );
+        assert_eq!(
+            Rule::interpolate("no placeholder", &path, Some(&cap)),
+            "no placeholder"
+        );
+    }
+
+    #[test]
+    fn test_gen_checked_with_mode() {
+        let rule: RuleSet = toml::from_str(
+            r#"
+[[rules]]
+name     = "forbid"
+pattern  = 'always\s'
+ignore   = '//.*'
+mode     = "forbid"
+hint     = ""
+includes = []
+
+[[rules]]
+name     = "check"
+pattern  = 'always\s'
+hint     = ""
+includes = []
+        "#,
+        )
+        .unwrap();
+        let src = "always @\n// always @\n";
+
+        let ignore = rule.rules[0].gen_ignore(src);
+        let checked = rule.rules[0].gen_checked(&PathBuf::from(""), src, &ignore);
+        assert_eq!(rule.rules[0].mode, Mode::Forbid);
+        assert_eq!(checked.len(), 2);
+        assert_eq!(checked[0].state, CheckedState::Fail);
+        assert_eq!(checked[1].state, CheckedState::Skip);
+
+        let checked = rule.rules[1].gen_checked(&PathBuf::from(""), src, &[]);
+        assert_eq!(rule.rules[1].mode, Mode::Check);
+        assert_eq!(checked.len(), 2);
+        assert_eq!(checked[0].state, CheckedState::Skip);
+        assert_eq!(
+            checked[0].reason.as_deref(),
+            Some("neither 'required' nor 'forbidden' is defined")
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let rule: RuleSet = toml::from_str(
+            r#"
+[[rules]]
+name      = "aaa"
+pattern   = 'bbb'
+forbidden = 'bbb'
+mode      = "forbid"
+hint      = ""
+includes  = []
+        "#,
+        )
+        .unwrap();
+        let err = rule.validate().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "'required' and 'forbidden' can't be used with mode = \"forbid\": 'aaa'"
+        );
+
+        let rule: RuleSet = toml::from_str(
+            r#"
+[[rules]]
+name      = "aaa"
+pattern   = 'bbb'
+required  = 'ccc'
+lines     = 1
+hint      = ""
+includes  = []
+        "#,
+        )
+        .unwrap();
+        let err = rule.validate().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "'lines' can't be used with mode = \"check\": 'aaa'"
+        );
+
+        let rule: RuleSet = toml::from_str(
+            r#"
+[[rules]]
+name      = "aaa"
+pattern   = 'bbb'
+mode      = "require"
+lines     = 1
+hint      = ""
+includes  = []
+        "#,
+        )
+        .unwrap();
+        assert!(rule.validate().is_ok());
+
+        let rule: RuleSet = toml::from_str(
+            r#"
+[[rules]]
+name      = "aaa"
+pattern   = 'bbb'
+min_count = 2
+max_count = 1
+hint      = ""
+includes  = []
+        "#,
+        )
+        .unwrap();
+        let err = rule.validate().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "'min_count' must not exceed 'max_count': 'aaa'"
+        );
+    }
+
+    #[test]
+    fn test_gen_checked_with_require() {
+        let rule: RuleSet = toml::from_str(
+            r#"
+[[rules]]
+name     = "copyright"
+pattern  = '(?m)^// Copyright \d+'
+ignore   = '/\*.*\*/'
+mode     = "require"
+lines    = 2
+hint     = "${file} must start with copyright header"
+includes = []
+        "#,
+        )
+        .unwrap();
+        let path = PathBuf::from("aaa.sv");
+        let check = |src: &str| {
+            let ignore = rule.rules[0].gen_ignore(src);
+            rule.rules[0].gen_checked(&path, src, &ignore)
+        };
+
+        let checked = check("\n// Copyright 2024\nmodule a;\n");
+        assert_eq!(checked.len(), 1);
+        assert_eq!(checked[0].state, CheckedState::Pass);
+        assert_eq!(checked[0].beg, 1);
+
+        let checked = check("\n\n// Copyright 2024\n");
+        assert_eq!(checked.len(), 1);
+        assert_eq!(checked[0].state, CheckedState::Fail);
+        assert_eq!(checked[0].beg, 0);
+        assert_eq!(checked[0].end, 0);
+        assert_eq!(checked[0].hint, "aaa.sv must start with copyright header");
+
+        let checked = check("/* // Copyright 2024 */\n");
+        assert_eq!(checked[0].state, CheckedState::Fail);
+
+        let checked = check("");
+        assert_eq!(checked[0].state, CheckedState::Fail);
+    }
+
+    #[test]
+    fn test_gen_checked_with_count() {
+        let rule: RuleSet = toml::from_str(
+            r#"
+[[rules]]
+name      = "one module"
+pattern   = '(?m)^module\s+(\w+)'
+ignore    = '//.*'
+min_count = 1
+max_count = 1
+hint      = "'${1}' must be in another file"
+includes  = []
+        "#,
+        )
+        .unwrap();
+        let path = PathBuf::from("aaa.sv");
+        let check = |src: &str| {
+            let ignore = rule.rules[0].gen_ignore(src);
+            rule.rules[0].gen_checked(&path, src, &ignore)
+        };
+
+        let checked = check("module a;\n// module b;\nendmodule\n");
+        assert_eq!(checked.len(), 1);
+        assert_eq!(checked[0].state, CheckedState::Pass);
+
+        let checked = check("module a;\nendmodule\n\nmodule b;\nendmodule\nmodule c;\n");
+        assert_eq!(checked.len(), 4);
+        assert_eq!(checked[3].state, CheckedState::Fail);
+        assert_eq!(checked[3].beg, 21);
+        assert_eq!(checked[3].end, 29);
+        assert_eq!(checked[3].hint, "'b' must be in another file");
+        assert_eq!(
+            checked[3].reason.as_deref(),
+            Some("found 3 occurrence(s) at line 1, 4, 6, at most 1 allowed")
+        );
+
+        let checked = check("// module a;\n");
+        assert_eq!(checked.len(), 1);
+        assert_eq!(checked[0].state, CheckedState::Fail);
+        assert_eq!(checked[0].beg, 0);
+        assert_eq!(
+            checked[0].reason.as_deref(),
+            Some("found no occurrence, at least 1 required")
+        );
+    }
 }
@@ -1,12 +1,15 @@
+mod fixer;
 mod lint;
 mod printer;
+mod walker;
 
-use crate::lint::RuleSet;
-use crate::printer::Printer;
+use crate::fixer::Fixer;
+use crate::lint::{normalize_path, RuleSet, Severity};
+use crate::printer::{ColumnUnit, Format, Printer};
 use anyhow::{format_err, Context, Error};
 use std::env;
 use std::fs::File;
-use std::io::Read;
+use std::io::{self, Read};
 use std::path::{Path, PathBuf};
 use std::process;
 use std::time::SystemTime;
@@ -34,9 +37,86 @@ pub struct Opt {
     #[structopt(short = "s", long = "simple")]
     pub simple: bool,
 
+    /// Output format
+    #[structopt(
+        short = "f",
+        long = "format",
+        default_value = "pretty",
+        possible_values = &["pretty", "simple", "json", "sarif", "junit", "checkstyle", "github", "gitlab"]
+    )]
+    pub format: Format,
+
+    /// Unit to count columns of reported positions
+    #[structopt(
+        long = "column-unit",
+        default_value = "char",
+        possible_values = &["char", "utf16", "byte"]
+    )]
+    pub column_unit: ColumnUnit,
+
+    /// Number of lines to show around failures in pretty format
+    #[structopt(short = "C", long = "context", default_value = "0")]
+    pub context: usize,
+
+    /// Number of lines to show after failures in pretty format [default: --context]
+    #[structopt(short = "A", long = "after-context")]
+    pub after_context: Option<usize>,
+
+    /// Number of lines to show before failures in pretty format [default: --context]
+    #[structopt(short = "B", long = "before-context")]
+    pub before_context: Option<usize>,
+
+    /// Minimum severity of failures to exit with error
+    #[structopt(
+        long = "fail-on",
+        default_value = "error",
+        possible_values = &["error", "warning", "info"]
+    )]
+    pub fail_on: Severity,
+
+    /// Fix failures by 'replace' of rules
+    #[structopt(long = "fix")]
+    pub fix: bool,
+
+    /// Show the changes of '--fix' as unified diff without modifying files
+    #[structopt(long = "fix-dry-run", alias = "diff", conflicts_with = "fix")]
+    pub fix_dry_run: bool,
+
+    /// Report suppression comments which don't suppress any failure
+    #[structopt(long = "report-unused-suppressions")]
+    pub report_unused_suppressions: bool,
+
     /// Show verbose message
     #[structopt(short = "v", long = "verbose")]
     pub verbose: bool,
+
+    /// Resolve globs of rules relative to the current directory instead of the rule file
+    #[structopt(long = "glob-from-cwd")]
+    pub glob_from_cwd: bool,
+
+    /// Don't respect ignore files (.gitignore/.ignore/.flexlintignore) and check build/VCS directories
+    #[structopt(long = "no-ignore")]
+    pub no_ignore: bool,
+
+    /// Number of threads to check files [default: number of CPUs]
+    #[structopt(short = "j", long = "jobs")]
+    pub jobs: Option<usize>,
+
+    /// Read source from stdin
+    #[structopt(
+        long = "stdin",
+        requires = "stdin-filename",
+        conflicts_with_all = &["fix", "fix-dry-run", "paths"]
+    )]
+    pub stdin: bool,
+
+    /// Virtual filename of source from stdin to select rules
+    #[structopt(long = "stdin-filename", requires = "stdin", parse(from_os_str))]
+    pub stdin_filename: Option<PathBuf>,
+
+    /// Files or directories to check [default: all files matched 'includes' of rules]
+    #[structopt(parse(from_os_str))]
+    pub paths: Vec<PathBuf>,
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -65,18 +145,79 @@ pub fn main() {
 
 #[cfg_attr(tarpaulin, skip)]
 pub fn run_opt(opt: &Opt) -> Result<bool, Error> {
-    let rule = search_rule(&opt.rule)?;
+    if let Some(jobs) = opt.jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build_global()?;
+    }
+
+    let rule_path = search_rule(&opt.rule)?;
 
-    let mut f = File::open(&rule)
-        .with_context(|| format!("failed to open: '{}'", rule.to_string_lossy()))?;
+    let mut f = File::open(&rule_path)
+        .with_context(|| format!("failed to open: '{}'", rule_path.to_string_lossy()))?;
     let mut s = String::new();
     let _ = f.read_to_string(&mut s);
-    let rule: RuleSet = toml::from_str(&s)
+    let mut rule: RuleSet = toml::from_str(&s)
         .with_context(|| format!("failed to parse toml: '{}'", opt.rule.to_string_lossy()))?;
+    rule.validate()
+        .with_context(|| format!("invalid rule: '{}'", opt.rule.to_string_lossy()))?;
 
-    let checked = rule.check()?;
+    rule.no_ignore = opt.no_ignore;
+    if !opt.glob_from_cwd {
+        if let Some(root) = rule_path.parent() {
+            rule.root = normalize_path(root);
+        }
+    }
+
+    let (checked, sources) = if let Some(ref path) = opt.stdin_filename {
+        let mut s = String::new();
+        io::stdin()
+            .read_to_string(&mut s)
+            .with_context(|| "failed to read stdin")?;
+        rule.check_source(path, s, opt.report_unused_suppressions)?
+    } else {
+        rule.check(&opt.paths, opt.report_unused_suppressions)?
+    };
+
+    if opt.fix_dry_run {
+        let (_, fixed) = Fixer::run(
+            &rule,
+            checked,
+            sources.clone(),
+            opt.report_unused_suppressions,
+        )?;
+        let diff = Fixer::diff(&sources, &fixed);
+        print!("{}", diff);
+        return Ok(diff.is_empty());
+    }
+
+    let (checked, sources) = if opt.fix {
+        let original = sources.clone();
+        let (checked, fixed) = Fixer::run(&rule, checked, sources, opt.report_unused_suppressions)?;
+        Fixer::write(&original, &fixed)?;
+        (checked, fixed)
+    } else {
+        (checked, sources)
+    };
+
+    let format = if opt.simple {
+        Format::Simple
+    } else {
+        opt.format
+    };
     let mut printer = Printer::new();
-    let pass = printer.print(checked, opt.simple, opt.verbose, SystemTime::now())?;
+    printer.column_unit = opt.column_unit;
+    printer.before_context = opt.before_context.unwrap_or(opt.context);
+    printer.after_context = opt.after_context.unwrap_or(opt.context);
+    let pass = printer.print(
+        &rule,
+        checked,
+        &sources,
+        format,
+        opt.fail_on,
+        opt.verbose,
+        SystemTime::now(),
+    )?;
 
     Ok(pass)
 }
@@ -1,13 +1,16 @@
-use crate::lint::{Checked, CheckedState};
-use anyhow::{Context, Error};
+use crate::lint::{Checked, CheckedState, RuleSet, Severity, Sources};
+use anyhow::{format_err, Error};
 use colored::*;
+use serde::Serialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
 use std::cmp;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::time::SystemTime;
 use term::{self, color, StdoutTerminal};
+use unicode_width::UnicodeWidthChar;
 
 // -------------------------------------------------------------------------------------------------
 // Color
@@ -36,13 +39,216 @@ enum Color {
 }
 
 // -------------------------------------------------------------------------------------------------
-// Printer
+// Format
+// -------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Pretty,
+    Simple,
+    Json,
+    Sarif,
+    Junit,
+    Checkstyle,
+    Github,
+    Gitlab,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pretty" => Ok(Format::Pretty),
+            "simple" => Ok(Format::Simple),
+            "json" => Ok(Format::Json),
+            "sarif" => Ok(Format::Sarif),
+            "junit" => Ok(Format::Junit),
+            "checkstyle" => Ok(Format::Checkstyle),
+            "github" => Ok(Format::Github),
+            "gitlab" => Ok(Format::Gitlab),
+            _ => Err(format_err!("unknown format: '{}'", s)),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// ColumnUnit
+// -------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ColumnUnit {
+    #[default]
+    Char,
+    Utf16,
+    Byte,
+}
+
+impl FromStr for ColumnUnit {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "char" => Ok(ColumnUnit::Char),
+            "utf16" => Ok(ColumnUnit::Utf16),
+            "byte" => Ok(ColumnUnit::Byte),
+            _ => Err(format_err!("unknown column unit: '{}'", s)),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// Json
+// -------------------------------------------------------------------------------------------------
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    checked: Vec<JsonChecked<'a>>,
+    summary: JsonSummary,
+}
+
+#[derive(Serialize)]
+struct JsonChecked<'a> {
+    #[serde(flatten)]
+    checked: &'a Checked,
+    line: usize,
+    column: usize,
+}
+
+#[derive(Serialize)]
+struct JsonSummary {
+    files: usize,
+    pass: usize,
+    fail: usize,
+    skip: usize,
+    error: usize,
+    warning: usize,
+    info: usize,
+}
+
+// -------------------------------------------------------------------------------------------------
+// LineIndex
 // -------------------------------------------------------------------------------------------------
 
 static CHAR_CR: u8 = 0x0d;
 static CHAR_LF: u8 = 0x0a;
 
+/// Start positions of lines to look up the position of a byte offset by binary search
+struct LineIndex<'a> {
+    src: &'a str,
+    starts: Vec<usize>,
+    unit: ColumnUnit,
+}
+
+impl<'a> LineIndex<'a> {
+    fn new(src: &'a str, unit: ColumnUnit) -> LineIndex<'a> {
+        let mut starts = vec![0];
+        for (i, c) in src.as_bytes().iter().enumerate() {
+            if *c == CHAR_LF {
+                starts.push(i + 1);
+            }
+        }
+        LineIndex { src, starts, unit }
+    }
+
+    /// Returns the number of lines except the empty line after the last LF
+    fn lines(&self) -> usize {
+        if self.starts.len() > 1 && self.starts[self.starts.len() - 1] == self.src.len() {
+            self.starts.len() - 1
+        } else {
+            self.starts.len()
+        }
+    }
+
+    fn line(&self, pos: usize) -> usize {
+        self.starts.partition_point(|x| *x <= pos)
+    }
+
+    /// Returns 1-based column of `pos` in `line` counted by `unit`
+    fn column(&self, line: usize, pos: usize) -> usize {
+        let beg = self.starts[line - 1];
+        let len = match self.src.get(beg..pos) {
+            Some(x) => match self.unit {
+                ColumnUnit::Char => x.chars().count(),
+                ColumnUnit::Utf16 => x.encode_utf16().count(),
+                ColumnUnit::Byte => x.len(),
+            },
+            None => pos - beg,
+        };
+        len + 1
+    }
+
+    /// Returns 1-based line and column of `pos`
+    fn position(&self, pos: usize) -> (usize, usize) {
+        let line = self.line(pos);
+        (line, self.column(line, pos))
+    }
+
+    /// Returns 1-based line and column next to the last character of `beg..end`
+    fn end_position(&self, beg: usize, end: usize) -> (usize, usize) {
+        if end > beg {
+            let line = self.line(end - 1);
+            (line, self.column(line, end))
+        } else {
+            self.position(beg)
+        }
+    }
+
+    /// Returns the range of 1-based `line` without line terminator
+    fn line_range(&self, line: usize) -> (usize, usize) {
+        let beg = self.starts[line - 1];
+        let mut end = match self.starts.get(line) {
+            Some(x) => x - 1,
+            None => self.src.len(),
+        };
+        if end > beg && self.src.as_bytes()[end - 1] == CHAR_CR {
+            end -= 1;
+        }
+        (beg, end)
+    }
+}
+
+static TAB_WIDTH: usize = 4;
+static MAX_SPAN_LINES: usize = 8;
+
+/// Returns the display width of `s` started at `offset` width
+fn display_width(s: &str, offset: usize) -> usize {
+    let mut width = offset;
+    for c in s.chars() {
+        if c == '\t' {
+            width += TAB_WIDTH - width % TAB_WIDTH;
+        } else {
+            width += UnicodeWidthChar::width(c).unwrap_or(0);
+        }
+    }
+    width - offset
+}
+
+/// Expands tabs to spaces to align with the width by `display_width`
+fn expand_tabs(s: &str) -> String {
+    let mut ret = String::new();
+    let mut width = 0;
+    for c in s.chars() {
+        if c == '\t' {
+            let n = TAB_WIDTH - width % TAB_WIDTH;
+            ret.push_str(&" ".repeat(n));
+            width += n;
+        } else {
+            ret.push(c);
+            width += UnicodeWidthChar::width(c).unwrap_or(0);
+        }
+    }
+    ret
+}
+
+// -------------------------------------------------------------------------------------------------
+// Printer
+// -------------------------------------------------------------------------------------------------
+
 pub struct Printer {
+    pub column_unit: ColumnUnit,
+    pub before_context: usize,
+    pub after_context: usize,
     term: Option<Box<StdoutTerminal>>,
 }
 
@@ -50,27 +256,39 @@ impl Printer {
     #[cfg_attr(tarpaulin, skip)]
     pub fn new() -> Printer {
         Printer {
+            column_unit: ColumnUnit::default(),
+            before_context: 0,
+            after_context: 0,
             term: term::stdout(),
         }
     }
 
     #[cfg_attr(tarpaulin, skip)]
+    #[allow(clippy::too_many_arguments)]
     pub fn print(
         &mut self,
+        ruleset: &RuleSet,
         checked: Vec<Checked>,
-        simple: bool,
+        sources: &Sources,
+        format: Format,
+        fail_on: Severity,
         verbose: bool,
         start_time: SystemTime,
     ) -> Result<bool, Error> {
         let path_checked = Printer::collect_by_path(checked);
 
-        let all_pass = if simple {
-            self.print_simple(&path_checked, verbose)?
-        } else {
-            self.print_pretty(&path_checked, verbose, start_time)?
-        };
+        match format {
+            Format::Simple => self.print_simple(&path_checked, sources, verbose)?,
+            Format::Pretty => self.print_pretty(&path_checked, sources, verbose, start_time)?,
+            Format::Json => self.print_json(&path_checked, sources)?,
+            Format::Sarif => self.print_sarif(ruleset, &path_checked, sources)?,
+            Format::Junit => self.print_junit(ruleset, &path_checked, sources)?,
+            Format::Checkstyle => self.print_checkstyle(&path_checked, sources)?,
+            Format::Github => self.print_github(&path_checked, sources)?,
+            Format::Gitlab => self.print_gitlab(&path_checked, sources)?,
+        }
 
-        Ok(all_pass)
+        Ok(Printer::is_all_pass(&path_checked, fail_on))
     }
 
     fn collect_by_path(checked: Vec<Checked>) -> Vec<(PathBuf, Vec<Checked>)> {
@@ -97,6 +315,126 @@ impl Printer {
         ret
     }
 
+    fn get_source<'a>(sources: &'a Sources, path: &Path) -> Result<&'a str, Error> {
+        match sources.get(path) {
+            Some(x) => Ok(x),
+            None => Err(format_err!(
+                "source not found: '{}'",
+                path.to_string_lossy()
+            )),
+        }
+    }
+
+    fn get_sources<'a>(
+        sources: &'a Sources,
+        path_checked: &[(PathBuf, Vec<Checked>)],
+    ) -> Result<Vec<&'a str>, Error> {
+        let mut ret = Vec::new();
+        for (path, _) in path_checked {
+            ret.push(Printer::get_source(sources, path)?);
+        }
+        Ok(ret)
+    }
+
+    fn severity_name(severity: Severity) -> &'static str {
+        match severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        }
+    }
+
+    fn sarif_level(severity: Severity) -> &'static str {
+        match severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "note",
+        }
+    }
+
+    fn sarif_column_kind(column_unit: ColumnUnit) -> Option<&'static str> {
+        match column_unit {
+            ColumnUnit::Char => Some("unicodeCodePoints"),
+            ColumnUnit::Utf16 => Some("utf16CodeUnits"),
+            ColumnUnit::Byte => None,
+        }
+    }
+
+    fn github_command(severity: Severity) -> &'static str {
+        match severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "notice",
+        }
+    }
+
+    fn gitlab_severity(severity: Severity) -> &'static str {
+        match severity {
+            Severity::Error => "major",
+            Severity::Warning => "minor",
+            Severity::Info => "info",
+        }
+    }
+
+    fn escape_xml(s: &str) -> String {
+        let mut ret = String::new();
+        for c in s.chars() {
+            match c {
+                '&' => ret.push_str("&amp;"),
+                '<' => ret.push_str("&lt;"),
+                '>' => ret.push_str("&gt;"),
+                '"' => ret.push_str("&quot;"),
+                _ => ret.push(c),
+            }
+        }
+        ret
+    }
+
+    fn escape_github_data(s: &str) -> String {
+        s.replace('%', "%25")
+            .replace('\r', "%0D")
+            .replace('\n', "%0A")
+    }
+
+    fn escape_github_property(s: &str) -> String {
+        Printer::escape_github_data(s)
+            .replace(':', "%3A")
+            .replace(',', "%2C")
+    }
+
+    fn count_state(path_checked: &[(PathBuf, Vec<Checked>)], state: CheckedState) -> usize {
+        path_checked.iter().fold(0, |sum, (_, y)| {
+            sum + y.iter().filter(|x| x.state == state).count()
+        })
+    }
+
+    fn count_fail(path_checked: &[(PathBuf, Vec<Checked>)], severity: Severity) -> usize {
+        path_checked.iter().fold(0, |sum, (_, y)| {
+            sum + y
+                .iter()
+                .filter(|x| x.state == CheckedState::Fail && x.severity == severity)
+                .count()
+        })
+    }
+
+    fn is_all_pass(path_checked: &[(PathBuf, Vec<Checked>)], fail_on: Severity) -> bool {
+        path_checked.iter().all(|(_, y)| {
+            y.iter()
+                .all(|x| x.state != CheckedState::Fail || x.severity < fail_on)
+        })
+    }
+
+    fn write_state(&mut self, checked: &Checked) {
+        match (&checked.state, checked.severity) {
+            (CheckedState::Pass, _) => self.write("Pass", Color::BrightGreen),
+            (CheckedState::Fail, Severity::Error) => self.write("Fail", Color::BrightRed),
+            (CheckedState::Fail, Severity::Warning) => self.write("Warn", Color::BrightYellow),
+            (CheckedState::Fail, Severity::Info) => self.write("Info", Color::BrightCyan),
+            (CheckedState::Skip, _) => self.write("Skip", Color::BrightMagenta),
+            _ => (),
+        }
+    }
+
     #[cfg_attr(tarpaulin, skip)]
     fn write(&mut self, dat: &str, color: Color) {
         if let Some(ref mut term) = self.term {
@@ -153,203 +491,269 @@ impl Printer {
     fn print_simple(
         &mut self,
         path_checked: &[(PathBuf, Vec<Checked>)],
+        sources: &Sources,
         verbose: bool,
-    ) -> Result<bool, Error> {
-        let mut all_pass = true;
-
+    ) -> Result<(), Error> {
         for (path, checked) in path_checked {
-            let mut f = File::open(&path)
-                .with_context(|| format!("failed to open: '{}'", path.to_string_lossy()))?;
-            let mut s = String::new();
-            let _ = f.read_to_string(&mut s);
-
-            let mut pos = 0;
-            let mut column = 1;
-            let mut last_lf = 0;
-            while pos < s.len() {
-                if s.as_bytes()[pos] == CHAR_LF {
-                    column += 1;
-                    last_lf = pos;
-                }
-
-                for checked in checked.iter() {
-                    if checked.state == CheckedState::Unmatch {
-                        continue;
-                    }
+            let s = Printer::get_source(sources, path)?;
+            let index = LineIndex::new(s, self.column_unit);
 
-                    if checked.beg == pos {
-                        if checked.state != CheckedState::Fail && !verbose {
-                            continue;
-                        }
-
-                        let row = pos - last_lf;
-                        let mut next_crlf = pos;
-                        while next_crlf < s.len() {
-                            if s.as_bytes()[next_crlf] == CHAR_CR
-                                || s.as_bytes()[next_crlf] == CHAR_LF
-                            {
-                                break;
-                            }
-                            next_crlf += 1;
-                        }
+            for checked in checked.iter() {
+                if checked.state == CheckedState::Unmatch {
+                    continue;
+                }
+                if checked.state != CheckedState::Fail && !verbose {
+                    continue;
+                }
 
-                        match checked.state {
-                            CheckedState::Pass => {
-                                self.write("Pass", Color::BrightGreen);
-                            }
-                            CheckedState::Fail => {
-                                all_pass = false;
-                                self.write("Fail", Color::BrightRed);
-                            }
-                            CheckedState::Skip => {
-                                self.write("Skip", Color::BrightMagenta);
-                            }
-                            _ => (),
-                        }
+                let (line, column) = index.position(checked.beg);
+                let (_, line_end) = index.line_range(line);
+                let text_end = cmp::max(checked.beg, line_end);
 
-                        self.write(
-                            &format!("\t{}:{}:{}", path.to_string_lossy(), column, row),
-                            Color::BrightBlue,
-                        );
+                self.write_state(checked);
 
-                        self.write(
-                            &format!(
-                                "\t{}",
-                                String::from_utf8_lossy(&s.as_bytes()[pos..next_crlf])
-                            ),
-                            Color::White,
-                        );
+                self.write(
+                    &format!("\t{}:{}:{}", path.to_string_lossy(), line, column),
+                    Color::BrightBlue,
+                );
 
-                        self.write(&format!("\thint: {}\n", checked.hint), Color::BrightYellow);
+                self.write(
+                    &format!(
+                        "\t{}",
+                        String::from_utf8_lossy(&s.as_bytes()[checked.beg..text_end])
+                    ),
+                    Color::White,
+                );
 
-                        self.write("", Color::Reset);
-                    }
+                if let Some(ref reason) = checked.reason {
+                    self.write(
+                        &format!("\thint: {}\treason: {}\n", checked.hint, reason),
+                        Color::BrightYellow,
+                    );
+                } else {
+                    self.write(&format!("\thint: {}\n", checked.hint), Color::BrightYellow);
                 }
-                pos += 1;
+
+                self.write("", Color::Reset);
             }
         }
-        Ok(all_pass)
+        Ok(())
     }
 
     #[cfg_attr(tarpaulin, skip)]
     fn print_pretty(
         &mut self,
         path_checked: &[(PathBuf, Vec<Checked>)],
+        sources: &Sources,
         verbose: bool,
         start_time: SystemTime,
-    ) -> Result<bool, Error> {
-        let mut all_pass = true;
-
+    ) -> Result<(), Error> {
         for (path, checked) in path_checked {
-            let mut f = File::open(&path)
-                .with_context(|| format!("failed to open: '{}'", path.to_string_lossy()))?;
-            let mut s = String::new();
-            let _ = f.read_to_string(&mut s);
-
-            let mut pos = 0;
-            let mut column = 1;
-            let mut last_lf = 0;
-            while pos < s.len() {
-                if s.as_bytes()[pos] == CHAR_LF {
-                    column += 1;
-                    last_lf = pos;
+            let s = Printer::get_source(sources, path)?;
+            let index = LineIndex::new(s, self.column_unit);
+
+            for checked in checked.iter() {
+                if checked.state == CheckedState::Unmatch {
+                    continue;
+                }
+                if checked.state != CheckedState::Fail && !verbose {
+                    continue;
                 }
 
-                for checked in checked.iter() {
-                    if checked.state == CheckedState::Unmatch {
-                        continue;
-                    }
-                    if checked.beg == pos {
-                        if checked.state != CheckedState::Fail && !verbose {
-                            continue;
-                        }
+                self.write_state(checked);
+                let layout = Printer::layout_pretty(
+                    path,
+                    s,
+                    &index,
+                    checked,
+                    self.before_context,
+                    self.after_context,
+                );
+                for (text, color) in layout {
+                    self.write(&text, color);
+                }
+            }
+        }
+        self.print_summary(path_checked, verbose, start_time)?;
 
-                        let row = pos - last_lf;
-                        let mut next_crlf = pos;
-                        while next_crlf < s.len() {
-                            if s.as_bytes()[next_crlf] == CHAR_CR
-                                || s.as_bytes()[next_crlf] == CHAR_LF
-                            {
-                                break;
-                            }
-                            next_crlf += 1;
-                        }
+        Ok(())
+    }
 
-                        match checked.state {
-                            CheckedState::Pass => {
-                                self.write("Pass", Color::BrightGreen);
-                            }
-                            CheckedState::Fail => {
-                                all_pass = false;
-                                self.write("Fail", Color::BrightRed);
-                            }
-                            CheckedState::Skip => {
-                                self.write("Skip", Color::BrightMagenta);
-                            }
-                            _ => (),
-                        }
+    /// Returns the colored texts of a result in pretty format following the state
+    fn layout_pretty(
+        path: &Path,
+        s: &str,
+        index: &LineIndex,
+        checked: &Checked,
+        before_context: usize,
+        after_context: usize,
+    ) -> Vec<(String, Color)> {
+        let (line, column) = index.position(checked.beg);
+        let end_line = if checked.end > checked.beg {
+            index.line(checked.end - 1)
+        } else {
+            line
+        };
+
+        let first_line = cmp::max(line.saturating_sub(before_context), 1);
+        let last_line = cmp::max(cmp::min(end_line + after_context, index.lines()), end_line);
+        let mark = if line == end_line { None } else { Some(" ") };
 
-                        let column_len = format!("{}", column).len();
+        let line_len = format!("{}", last_line).len();
 
-                        self.write(&format!(": {}\n", checked.name), Color::BrightWhite);
+        let mut ret = vec![
+            (format!(": {}\n", checked.name), Color::BrightWhite),
+            (String::from("   -->"), Color::BrightBlue),
+            (
+                format!(" {}:{}:{}\n", path.to_string_lossy(), line, column),
+                Color::White,
+            ),
+            (
+                format!("{}|\n", " ".repeat(line_len + 1)),
+                Color::BrightBlue,
+            ),
+        ];
 
-                        self.write("   -->", Color::BrightBlue);
+        for x in first_line..line {
+            let (beg, end) = index.line_range(x);
+            Printer::layout_source_line(&mut ret, x, line_len, mark, s.get(beg..end).unwrap_or(""));
+        }
 
-                        self.write(
-                            &format!(" {}:{}:{}\n", path.to_string_lossy(), column, row),
-                            Color::White,
-                        );
+        if line == end_line {
+            Printer::layout_line_span(&mut ret, s, index, checked, line, line_len);
+        } else {
+            Printer::layout_multi_line_span(&mut ret, s, index, checked, line, end_line, line_len);
+        }
+
+        if checked.state == CheckedState::Fail {
+            ret.push((format!(" hint: {}\n", checked.hint), Color::BrightYellow));
+            if let Some(ref reason) = checked.reason {
+                ret.push((format!("{}=", " ".repeat(line_len + 1)), Color::BrightBlue));
+                ret.push((format!(" note: {}\n", reason), Color::BrightMagenta));
+            }
+        } else if let Some(ref reason) = checked.reason {
+            ret.push((format!(" reason: {}\n", reason), Color::BrightMagenta));
+        } else {
+            ret.push((String::from("\n"), Color::BrightYellow));
+        }
+
+        for x in end_line + 1..=last_line {
+            let (beg, end) = index.line_range(x);
+            Printer::layout_source_line(&mut ret, x, line_len, mark, s.get(beg..end).unwrap_or(""));
+        }
+
+        ret.push((String::from("\n"), Color::Reset));
+        ret
+    }
 
-                        self.write(
-                            &format!("{}|\n", " ".repeat(column_len + 1)),
-                            Color::BrightBlue,
-                        );
+    fn layout_source_line(
+        ret: &mut Vec<(String, Color)>,
+        line: usize,
+        line_len: usize,
+        mark: Option<&str>,
+        text: &str,
+    ) {
+        ret.push((format!("{:>1$} |", line, line_len), Color::BrightBlue));
+        if let Some(mark) = mark {
+            ret.push((format!(" {}", mark), Color::BrightYellow));
+        }
+        ret.push((format!(" {}\n", expand_tabs(text)), Color::White));
+    }
 
-                        self.write(&format!("{} |", column), Color::BrightBlue);
+    fn layout_line_span(
+        ret: &mut Vec<(String, Color)>,
+        s: &str,
+        index: &LineIndex,
+        checked: &Checked,
+        line: usize,
+        line_len: usize,
+    ) {
+        let (line_beg, line_end) = index.line_range(line);
+        let text = s.get(line_beg..line_end).unwrap_or("");
+        Printer::layout_source_line(ret, line, line_len, None, text);
 
-                        let substring = if last_lf == next_crlf {
-                            "".into()
-                        } else {
-                            String::from_utf8_lossy(&s.as_bytes()[last_lf + 1..next_crlf])
-                                .into_owned()
-                        };
+        ret.push((format!("{}|", " ".repeat(line_len + 1)), Color::BrightBlue));
 
-                        self.write(&format!(" {}\n", substring), Color::White);
+        // Carets are aligned by display width because columns may be counted by another unit
+        let caret_end = cmp::max(cmp::min(checked.end, line_end), checked.beg);
+        let prefix = s.get(line_beg..checked.beg).unwrap_or("");
+        let matched = s.get(checked.beg..caret_end).unwrap_or("");
+        let space_count = display_width(prefix, 0);
+        // Empty spans such as failures of 'require' mode are marked by a caret too
+        let caret_count = cmp::max(display_width(matched, space_count), 1);
 
-                        self.write(
-                            &format!("{}|", " ".repeat(column_len + 1)),
-                            Color::BrightBlue,
-                        );
+        ret.push((
+            format!(" {}{}", " ".repeat(space_count), "^".repeat(caret_count)),
+            Color::BrightYellow,
+        ));
+    }
 
-                        let space_count = if pos > last_lf { pos - last_lf - 1 } else { 0 };
+    /// Lays out the lines of a span with a left bracket like rustc
+    fn layout_multi_line_span(
+        ret: &mut Vec<(String, Color)>,
+        s: &str,
+        index: &LineIndex,
+        checked: &Checked,
+        line: usize,
+        end_line: usize,
+        line_len: usize,
+    ) {
+        let (line_beg, line_end) = index.line_range(line);
+        let text = s.get(line_beg..line_end).unwrap_or("");
+        let prefix = s.get(line_beg..checked.beg).unwrap_or("");
+        let space_count = display_width(prefix, 0);
 
-                        self.write(
-                            &format!(
-                                " {}{}",
-                                " ".repeat(space_count),
-                                "^".repeat(cmp::min(checked.end, next_crlf) - checked.beg)
-                            ),
-                            Color::BrightYellow,
-                        );
+        // The span started at the indentation is marked on the first line directly
+        if prefix.trim().is_empty() {
+            Printer::layout_source_line(ret, line, line_len, Some("/"), text);
+        } else {
+            Printer::layout_source_line(ret, line, line_len, Some(" "), text);
+            ret.push((format!("{}|", " ".repeat(line_len + 1)), Color::BrightBlue));
+            ret.push((
+                format!("  {}^\n", "_".repeat(space_count + 1)),
+                Color::BrightYellow,
+            ));
+        }
 
-                        if checked.state == CheckedState::Fail {
-                            self.write(
-                                &format!(" hint: {}\n\n", checked.hint),
-                                Color::BrightYellow,
-                            );
-                        } else {
-                            self.write("\n\n", Color::BrightYellow);
-                        }
+        // Lines in the middle of a long span are omitted
+        let omitted = if end_line - line > MAX_SPAN_LINES {
+            Some((line + MAX_SPAN_LINES / 2, end_line - MAX_SPAN_LINES / 2))
+        } else {
+            None
+        };
 
-                        self.write("", Color::Reset);
-                    }
+        for x in line + 1..=end_line {
+            if let Some((omit_beg, omit_end)) = omitted {
+                if x == omit_beg {
+                    ret.push((format!("{} |", " ".repeat(line_len)), Color::BrightBlue));
+                    ret.push((String::from(" |"), Color::BrightYellow));
+                    ret.push((String::from(" ...\n"), Color::White));
+                }
+                if omit_beg <= x && x < omit_end {
+                    continue;
                 }
-                pos += 1;
             }
+
+            let (beg, end) = index.line_range(x);
+            Printer::layout_source_line(ret, x, line_len, Some("|"), s.get(beg..end).unwrap_or(""));
         }
-        let _ = self.print_summary(path_checked, verbose, start_time)?;
 
-        Ok(all_pass)
+        // The underline reaches the beginning of the last character which may be multibyte
+        let (end_line_beg, end_line_end) = index.line_range(end_line);
+        let last = s
+            .get(..checked.end)
+            .and_then(|x| x.char_indices().next_back())
+            .map(|(i, _)| i)
+            .unwrap_or(end_line_beg);
+        let last = cmp::max(cmp::min(last, end_line_end), end_line_beg);
+        let prefix = s.get(end_line_beg..last).unwrap_or("");
+        let underline_count = display_width(prefix, 0) + 1;
+
+        ret.push((format!("{}|", " ".repeat(line_len + 1)), Color::BrightBlue));
+        ret.push((
+            format!(" |{}^", "_".repeat(underline_count)),
+            Color::BrightYellow,
+        ));
     }
 
     #[cfg_attr(tarpaulin, skip)]
@@ -365,16 +769,13 @@ impl Printer {
         );
 
         let cnt_file = path_checked.len();
-        let cnt_pass = path_checked.iter().fold(0, |sum, (_, y)| {
-            sum + y.iter().filter(|x| x.state == CheckedState::Pass).count()
-        });
-        let cnt_fail = path_checked.iter().fold(0, |sum, (_, y)| {
-            sum + y.iter().filter(|x| x.state == CheckedState::Fail).count()
-        });
-        let cnt_skip = path_checked.iter().fold(0, |sum, (_, y)| {
-            sum + y.iter().filter(|x| x.state == CheckedState::Skip).count()
-        });
+        let cnt_pass = Printer::count_state(path_checked, CheckedState::Pass);
+        let cnt_fail = Printer::count_state(path_checked, CheckedState::Fail);
+        let cnt_skip = Printer::count_state(path_checked, CheckedState::Skip);
         let cnt_checked = cnt_pass + cnt_fail + cnt_skip;
+        let cnt_error = Printer::count_fail(path_checked, Severity::Error);
+        let cnt_warning = Printer::count_fail(path_checked, Severity::Warning);
+        let cnt_info = Printer::count_fail(path_checked, Severity::Info);
 
         self.write(&format!("  * Checked files : {}\n", cnt_file), Color::Reset);
         self.write(
@@ -384,6 +785,13 @@ impl Printer {
             ),
             Color::Reset,
         );
+        self.write(
+            &format!(
+                "  * Failed points : {} ( Error: {}, Warning: {}, Info: {} )\n",
+                cnt_fail, cnt_error, cnt_warning, cnt_info
+            ),
+            Color::Reset,
+        );
 
         let elapsed = start_time.elapsed()?;
         let elapsed = elapsed.as_secs() as f64 + elapsed.subsec_micros() as f64 * 1e-6;
@@ -392,6 +800,434 @@ impl Printer {
 
         Ok(())
     }
+
+    #[cfg_attr(tarpaulin, skip)]
+    fn print_json(
+        &mut self,
+        path_checked: &[(PathBuf, Vec<Checked>)],
+        sources: &Sources,
+    ) -> Result<(), Error> {
+        let sources = Printer::get_sources(sources, path_checked)?;
+        println!(
+            "{}",
+            Printer::format_json(path_checked, &sources, self.column_unit)?
+        );
+
+        Ok(())
+    }
+
+    fn format_json(
+        path_checked: &[(PathBuf, Vec<Checked>)],
+        sources: &[&str],
+        column_unit: ColumnUnit,
+    ) -> Result<String, Error> {
+        // All the checked points are included regardless of '--verbose' to match the summary
+        let mut checked = Vec::new();
+        for ((_, c), s) in path_checked.iter().zip(sources) {
+            let index = LineIndex::new(s, column_unit);
+            for c in c {
+                if c.state == CheckedState::Unmatch {
+                    continue;
+                }
+
+                let (line, column) = index.position(c.beg);
+                checked.push(JsonChecked {
+                    checked: c,
+                    line,
+                    column,
+                });
+            }
+        }
+
+        let report = JsonReport {
+            checked,
+            summary: JsonSummary {
+                files: path_checked.len(),
+                pass: Printer::count_state(path_checked, CheckedState::Pass),
+                fail: Printer::count_state(path_checked, CheckedState::Fail),
+                skip: Printer::count_state(path_checked, CheckedState::Skip),
+                error: Printer::count_fail(path_checked, Severity::Error),
+                warning: Printer::count_fail(path_checked, Severity::Warning),
+                info: Printer::count_fail(path_checked, Severity::Info),
+            },
+        };
+
+        Ok(serde_json::to_string_pretty(&report)?)
+    }
+
+    #[cfg_attr(tarpaulin, skip)]
+    fn print_sarif(
+        &mut self,
+        ruleset: &RuleSet,
+        path_checked: &[(PathBuf, Vec<Checked>)],
+        sources: &Sources,
+    ) -> Result<(), Error> {
+        let sources = Printer::get_sources(sources, path_checked)?;
+        println!(
+            "{}",
+            Printer::format_sarif(ruleset, path_checked, &sources, self.column_unit)?
+        );
+
+        Ok(())
+    }
+
+    fn format_sarif(
+        ruleset: &RuleSet,
+        path_checked: &[(PathBuf, Vec<Checked>)],
+        sources: &[&str],
+        column_unit: ColumnUnit,
+    ) -> Result<String, Error> {
+        let rules: Vec<_> = ruleset
+            .rules
+            .iter()
+            .map(|r| {
+                json!({
+                    "id": r.name,
+                    "name": r.name,
+                    "help": { "text": r.hint },
+                    "defaultConfiguration": {
+                        "level": Printer::sarif_level(r.severity),
+                    },
+                })
+            })
+            .collect();
+
+        let mut results = Vec::new();
+        for ((path, c), s) in path_checked.iter().zip(sources) {
+            let index = LineIndex::new(s, column_unit);
+            for c in c {
+                if c.state != CheckedState::Fail {
+                    continue;
+                }
+
+                let (start_line, start_column) = index.position(c.beg);
+                let (end_line, end_column) = index.end_position(c.beg, c.end);
+                let mut result = json!({
+                    "ruleId": c.rule,
+                    "level": Printer::sarif_level(c.severity),
+                    "message": { "text": c.hint },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": {
+                                "uri": path.to_string_lossy().replace('\\', "/"),
+                            },
+                            "region": {
+                                "startLine": start_line,
+                                "startColumn": start_column,
+                                "endLine": end_line,
+                                "endColumn": end_column,
+                            },
+                        },
+                    }],
+                });
+
+                // Results not from the rules (e.g. unused suppression) have no 'ruleIndex'
+                if let Some(i) = ruleset.rules.iter().position(|r| r.name == c.rule) {
+                    result["ruleIndex"] = json!(i);
+                }
+                results.push(result);
+            }
+        }
+
+        let log = json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": env!("CARGO_PKG_NAME"),
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                        "rules": rules,
+                    },
+                },
+                "results": results,
+            }],
+        });
+
+        let mut log = log;
+        if let Some(kind) = Printer::sarif_column_kind(column_unit) {
+            log["runs"][0]["columnKind"] = json!(kind);
+        }
+
+        Ok(serde_json::to_string_pretty(&log)?)
+    }
+
+    #[cfg_attr(tarpaulin, skip)]
+    fn print_junit(
+        &mut self,
+        ruleset: &RuleSet,
+        path_checked: &[(PathBuf, Vec<Checked>)],
+        sources: &Sources,
+    ) -> Result<(), Error> {
+        let sources = Printer::get_sources(sources, path_checked)?;
+        print!(
+            "{}",
+            Printer::format_junit(ruleset, path_checked, &sources, self.column_unit)
+        );
+
+        Ok(())
+    }
+
+    fn format_junit(
+        ruleset: &RuleSet,
+        path_checked: &[(PathBuf, Vec<Checked>)],
+        sources: &[&str],
+        column_unit: ColumnUnit,
+    ) -> String {
+        let indexes: Vec<_> = sources
+            .iter()
+            .map(|x| LineIndex::new(x, column_unit))
+            .collect();
+
+        let mut suites = String::new();
+        let mut all_tests = 0;
+        let mut all_failures = 0;
+        let mut all_skipped = 0;
+
+        // Results not from the rules (e.g. unused suppression) are reported as additional testsuites
+        let mut names: Vec<_> = ruleset.rules.iter().map(|r| r.name.as_str()).collect();
+        for (_, c) in path_checked {
+            for c in c {
+                if !names.contains(&c.rule.as_str()) {
+                    names.push(&c.rule);
+                }
+            }
+        }
+
+        for name in names {
+            let mut cases = String::new();
+            let mut tests = 0;
+            let mut failures = 0;
+            let mut skipped = 0;
+
+            for ((path, c), index) in path_checked.iter().zip(&indexes) {
+                for c in c {
+                    if c.state == CheckedState::Unmatch || c.rule != name {
+                        continue;
+                    }
+
+                    let (line, column) = index.position(c.beg);
+                    let location = Printer::escape_xml(&format!(
+                        "{}:{}:{}",
+                        path.to_string_lossy(),
+                        line,
+                        column
+                    ));
+
+                    tests += 1;
+                    cases.push_str(&format!(
+                        "    <testcase name=\"{}\" classname=\"{}\"",
+                        location,
+                        Printer::escape_xml(name)
+                    ));
+                    match c.state {
+                        CheckedState::Fail => {
+                            failures += 1;
+                            cases.push_str(&format!(
+                                ">\n      <failure message=\"{}\" type=\"{}\">{}</failure>\n    </testcase>\n",
+                                Printer::escape_xml(&c.hint),
+                                Printer::severity_name(c.severity),
+                                location
+                            ));
+                        }
+                        CheckedState::Skip => {
+                            skipped += 1;
+                            match c.reason {
+                                Some(ref reason) => cases.push_str(&format!(
+                                    ">\n      <skipped message=\"{}\"/>\n    </testcase>\n",
+                                    Printer::escape_xml(reason)
+                                )),
+                                None => cases.push_str(">\n      <skipped/>\n    </testcase>\n"),
+                            }
+                        }
+                        _ => cases.push_str("/>\n"),
+                    }
+                }
+            }
+
+            suites.push_str(&format!(
+                "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\">\n{}  </testsuite>\n",
+                Printer::escape_xml(name),
+                tests,
+                failures,
+                skipped,
+                cases
+            ));
+
+            all_tests += tests;
+            all_failures += failures;
+            all_skipped += skipped;
+        }
+
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\">\n{}</testsuites>\n",
+            env!("CARGO_PKG_NAME"),
+            all_tests,
+            all_failures,
+            all_skipped,
+            suites
+        )
+    }
+
+    #[cfg_attr(tarpaulin, skip)]
+    fn print_checkstyle(
+        &mut self,
+        path_checked: &[(PathBuf, Vec<Checked>)],
+        sources: &Sources,
+    ) -> Result<(), Error> {
+        let sources = Printer::get_sources(sources, path_checked)?;
+        print!(
+            "{}",
+            Printer::format_checkstyle(path_checked, &sources, self.column_unit)
+        );
+
+        Ok(())
+    }
+
+    fn format_checkstyle(
+        path_checked: &[(PathBuf, Vec<Checked>)],
+        sources: &[&str],
+        column_unit: ColumnUnit,
+    ) -> String {
+        let mut files = String::new();
+        for ((path, c), s) in path_checked.iter().zip(sources) {
+            let index = LineIndex::new(s, column_unit);
+            files.push_str(&format!(
+                "  <file name=\"{}\">\n",
+                Printer::escape_xml(&path.to_string_lossy())
+            ));
+            for c in c {
+                if c.state != CheckedState::Fail {
+                    continue;
+                }
+
+                let (line, column) = index.position(c.beg);
+                files.push_str(&format!(
+                    "    <error line=\"{}\" column=\"{}\" severity=\"{}\" message=\"{}\" source=\"{}\"/>\n",
+                    line,
+                    column,
+                    Printer::severity_name(c.severity),
+                    Printer::escape_xml(&c.hint),
+                    Printer::escape_xml(&c.rule)
+                ));
+            }
+            files.push_str("  </file>\n");
+        }
+
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<checkstyle version=\"4.3\">\n{}</checkstyle>\n",
+            files
+        )
+    }
+
+    #[cfg_attr(tarpaulin, skip)]
+    fn print_github(
+        &mut self,
+        path_checked: &[(PathBuf, Vec<Checked>)],
+        sources: &Sources,
+    ) -> Result<(), Error> {
+        let sources = Printer::get_sources(sources, path_checked)?;
+        print!(
+            "{}",
+            Printer::format_github(path_checked, &sources, self.column_unit)
+        );
+
+        Ok(())
+    }
+
+    fn format_github(
+        path_checked: &[(PathBuf, Vec<Checked>)],
+        sources: &[&str],
+        column_unit: ColumnUnit,
+    ) -> String {
+        let mut ret = String::new();
+        for ((path, c), s) in path_checked.iter().zip(sources) {
+            let index = LineIndex::new(s, column_unit);
+            for c in c {
+                if c.state != CheckedState::Fail {
+                    continue;
+                }
+
+                let (line, column) = index.position(c.beg);
+                let (end_line, end_column) = index.end_position(c.beg, c.end);
+                ret.push_str(&format!(
+                    "::{} file={},line={},col={},endLine={},endColumn={},title={}::{}\n",
+                    Printer::github_command(c.severity),
+                    Printer::escape_github_property(&path.to_string_lossy()),
+                    line,
+                    column,
+                    end_line,
+                    end_column,
+                    Printer::escape_github_property(&c.name),
+                    Printer::escape_github_data(&c.hint)
+                ));
+            }
+        }
+        ret
+    }
+
+    #[cfg_attr(tarpaulin, skip)]
+    fn print_gitlab(
+        &mut self,
+        path_checked: &[(PathBuf, Vec<Checked>)],
+        sources: &Sources,
+    ) -> Result<(), Error> {
+        let sources = Printer::get_sources(sources, path_checked)?;
+        println!("{}", Printer::format_gitlab(path_checked, &sources)?);
+
+        Ok(())
+    }
+
+    fn format_gitlab(
+        path_checked: &[(PathBuf, Vec<Checked>)],
+        sources: &[&str],
+    ) -> Result<String, Error> {
+        let mut issues = Vec::new();
+        for ((path, c), s) in path_checked.iter().zip(sources) {
+            let index = LineIndex::new(s, ColumnUnit::default());
+            let path = path.to_string_lossy().replace('\\', "/");
+
+            // The same text can be matched several times in a file,
+            // so the occurrence count is added to keep fingerprints unique.
+            let mut occurrences: HashMap<(&str, &str), usize> = HashMap::new();
+
+            for c in c {
+                if c.state != CheckedState::Fail {
+                    continue;
+                }
+
+                let text = s.get(c.beg..c.end).unwrap_or("");
+                let occurrence = occurrences.entry((&c.rule, text)).or_insert(0);
+                *occurrence += 1;
+
+                let mut hasher = Sha256::new();
+                for x in &[c.rule.as_str(), &path, text, &occurrence.to_string()] {
+                    hasher.update(x.as_bytes());
+                    hasher.update([0]);
+                }
+                let fingerprint: String = hasher
+                    .finalize()
+                    .iter()
+                    .map(|x| format!("{:02x}", x))
+                    .collect();
+
+                let (line, _) = index.position(c.beg);
+                issues.push(json!({
+                    "description": c.hint,
+                    "check_name": c.rule,
+                    "fingerprint": fingerprint,
+                    "severity": Printer::gitlab_severity(c.severity),
+                    "location": {
+                        "path": path,
+                        "lines": { "begin": line },
+                    },
+                }));
+            }
+        }
+
+        Ok(serde_json::to_string_pretty(&issues)?)
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -402,45 +1238,36 @@ impl Printer {
 mod tests {
     use super::*;
 
+    fn gen_checked(
+        path: &str,
+        beg: usize,
+        end: usize,
+        state: CheckedState,
+        rule: &str,
+        hint: &str,
+    ) -> Checked {
+        Checked {
+            path: PathBuf::from(path),
+            beg,
+            end,
+            state,
+            severity: Severity::Error,
+            rule: String::from(rule),
+            name: String::from(rule),
+            hint: String::from(hint),
+            reason: None,
+            replace: None,
+        }
+    }
+
     #[test]
     fn test_collect_by_path() {
-        let mut checked = Vec::new();
-
-        checked.push(Checked {
-            path: PathBuf::from("bbb"),
-            beg: 100,
-            end: 200,
-            state: CheckedState::Pass,
-            name: String::from(""),
-            hint: String::from(""),
-        });
-
-        checked.push(Checked {
-            path: PathBuf::from("aaa"),
-            beg: 10,
-            end: 20,
-            state: CheckedState::Pass,
-            name: String::from(""),
-            hint: String::from(""),
-        });
-
-        checked.push(Checked {
-            path: PathBuf::from("aaa"),
-            beg: 0,
-            end: 10,
-            state: CheckedState::Pass,
-            name: String::from(""),
-            hint: String::from(""),
-        });
-
-        checked.push(Checked {
-            path: PathBuf::from("bbb"),
-            beg: 20,
-            end: 30,
-            state: CheckedState::Pass,
-            name: String::from(""),
-            hint: String::from(""),
-        });
+        let checked = vec![
+            gen_checked("bbb", 100, 200, CheckedState::Pass, "", ""),
+            gen_checked("aaa", 10, 20, CheckedState::Pass, "", ""),
+            gen_checked("aaa", 0, 10, CheckedState::Pass, "", ""),
+            gen_checked("bbb", 20, 30, CheckedState::Pass, "", ""),
+        ];
 
         let path_checked = Printer::collect_by_path(checked);
 
@@ -451,4 +1278,358 @@ mod tests {
         assert_eq!(path_checked[1].1[0].beg, 20);
         assert_eq!(path_checked[1].1[1].beg, 100);
     }
+
+    #[test]
+    fn test_format_json() {
+        let src = String::from("aaa\nbbb ccc\n");
+        let checked = vec![
+            gen_checked("aaa", 8, 11, CheckedState::Fail, "ccc", "ddd"),
+            gen_checked("aaa", 0, 3, CheckedState::Pass, "ccc", "ddd"),
+        ];
+
+        let path_checked = Printer::collect_by_path(checked);
+        let json = Printer::format_json(&path_checked, &[src.as_str()], ColumnUnit::Char).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(json["checked"].as_array().unwrap().len(), 2);
+        assert_eq!(json["checked"][0]["state"], "pass");
+        assert_eq!(json["checked"][1]["path"], "aaa");
+        assert_eq!(json["checked"][1]["state"], "fail");
+        assert_eq!(json["checked"][1]["name"], "ccc");
+        assert_eq!(json["checked"][1]["hint"], "ddd");
+        assert_eq!(json["checked"][1]["beg"], 8);
+        assert_eq!(json["checked"][1]["line"], 2);
+        assert_eq!(json["checked"][1]["column"], 5);
+        assert_eq!(json["summary"]["pass"], 1);
+        assert_eq!(json["summary"]["fail"], 1);
+    }
+
+    #[test]
+    fn test_format_sarif() {
+        let ruleset: RuleSet = toml::from_str(
+            r#"
+[[rules]]
+name     = "aaa"
+pattern  = 'bbb'
+hint     = "ccc"
+includes = ["ddd"]
+            "#,
+        )
+        .unwrap();
+
+        let src = String::from("aaa\nbbb ccc\n");
+        let checked = vec![
+            gen_checked("eee", 4, 7, CheckedState::Fail, "aaa", "ccc"),
+            Checked {
+                severity: Severity::Warning,
+                ..gen_checked(
+                    "eee",
+                    8,
+                    11,
+                    CheckedState::Fail,
+                    "unused suppression",
+                    "fff",
+                )
+            },
+        ];
+
+        let path_checked = Printer::collect_by_path(checked);
+        let sarif =
+            Printer::format_sarif(&ruleset, &path_checked, &[src.as_str()], ColumnUnit::Char)
+                .unwrap();
+        let sarif: serde_json::Value = serde_json::from_str(&sarif).unwrap();
+
+        let run = &sarif["runs"][0];
+        assert_eq!(sarif["version"], "2.1.0");
+        assert_eq!(run["tool"]["driver"]["rules"][0]["name"], "aaa");
+        assert_eq!(run["tool"]["driver"]["rules"][0]["help"]["text"], "ccc");
+        assert_eq!(run["results"][0]["ruleIndex"], 0);
+        assert_eq!(run["results"][0]["message"]["text"], "ccc");
+        assert_eq!(run["results"][1]["ruleId"], "unused suppression");
+        assert!(run["results"][1].get("ruleIndex").is_none());
+
+        let location = &run["results"][0]["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "eee");
+        assert_eq!(location["region"]["startLine"], 2);
+        assert_eq!(location["region"]["endLine"], 2);
+    }
+
+    #[test]
+    fn test_format_junit() {
+        let ruleset: RuleSet = toml::from_str(
+            r#"
+[[rules]]
+name     = "a<a"
+pattern  = 'bbb'
+hint     = "c&c"
+includes = ["ddd"]
+            "#,
+        )
+        .unwrap();
+
+        let src = String::from("aaa\nbbb bbb\n");
+        let checked = vec![
+            gen_checked("eee", 4, 7, CheckedState::Fail, "a<a", "c&c"),
+            gen_checked("eee", 8, 11, CheckedState::Pass, "a<a", "c&c"),
+            Checked {
+                severity: Severity::Warning,
+                ..gen_checked("eee", 0, 3, CheckedState::Fail, "unused suppression", "fff")
+            },
+        ];
+
+        let path_checked = Printer::collect_by_path(checked);
+        let junit =
+            Printer::format_junit(&ruleset, &path_checked, &[src.as_str()], ColumnUnit::Char);
+
+        assert!(
+            junit.contains(r#"<testsuites name="flexlint" tests="3" failures="2" skipped="0">"#)
+        );
+        assert!(junit.contains(r#"<testsuite name="a&lt;a" tests="2" failures="1" skipped="0">"#));
+        assert!(junit.contains(
+            r#"<testsuite name="unused suppression" tests="1" failures="1" skipped="0">"#
+        ));
+        assert!(junit.contains(r#"<failure message="c&amp;c" type="error">eee:2:1</failure>"#));
+        assert!(junit.contains(r#"<testcase name="eee:2:5" classname="a&lt;a"/>"#));
+    }
+
+    #[test]
+    fn test_format_checkstyle() {
+        let src = String::from("aaa\nbbb ccc\n");
+        let checked = vec![
+            gen_checked("eee", 8, 11, CheckedState::Fail, "a<a", "c&c"),
+            gen_checked("eee", 0, 3, CheckedState::Pass, "a<a", "c&c"),
+        ];
+
+        let path_checked = Printer::collect_by_path(checked);
+        let checkstyle =
+            Printer::format_checkstyle(&path_checked, &[src.as_str()], ColumnUnit::Char);
+
+        assert!(checkstyle.contains(r#"<file name="eee">"#));
+        assert!(checkstyle.contains(
+            r#"<error line="2" column="5" severity="error" message="c&amp;c" source="a&lt;a"/>"#
+        ));
+        assert_eq!(checkstyle.matches("<error ").count(), 1);
+    }
+
+    #[test]
+    fn test_format_github() {
+        let src = String::from("aaa\nbbb ccc\n");
+        let checked = vec![gen_checked(
+            "eee",
+            8,
+            11,
+            CheckedState::Fail,
+            "a:a, a",
+            "100%",
+        )];
+
+        let path_checked = Printer::collect_by_path(checked);
+        let github = Printer::format_github(&path_checked, &[src.as_str()], ColumnUnit::Char);
+
+        assert_eq!(
+            github,
+            "::error file=eee,line=2,col=5,endLine=2,endColumn=8,title=a%3Aa%2C a::100%25\n"
+        );
+    }
+
+    #[test]
+    fn test_format_gitlab() {
+        let checked = vec![
+            gen_checked("eee", 0, 3, CheckedState::Fail, "aaa", "ccc"),
+            gen_checked("eee", 8, 11, CheckedState::Fail, "aaa", "ccc"),
+        ];
+        let path_checked = Printer::collect_by_path(checked);
+
+        let gitlab = Printer::format_gitlab(&path_checked, &["bbb\nbbb bbb\n"]);
+        let gitlab: serde_json::Value = serde_json::from_str(&gitlab.unwrap()).unwrap();
+        assert_eq!(gitlab[0]["description"], "ccc");
+        assert_eq!(gitlab[0]["check_name"], "aaa");
+        assert_eq!(gitlab[0]["location"]["path"], "eee");
+        assert_eq!(gitlab[0]["location"]["lines"]["begin"], 1);
+        assert_eq!(gitlab[1]["location"]["lines"]["begin"], 2);
+        assert_ne!(gitlab[0]["fingerprint"], gitlab[1]["fingerprint"]);
+
+        // fingerprints don't depend on byte offsets
+        let checked = vec![gen_checked("eee", 4, 7, CheckedState::Fail, "aaa", "ccc")];
+        let path_checked = Printer::collect_by_path(checked);
+
+        let moved = Printer::format_gitlab(&path_checked, &["\n\n\n\nbbb\n"]);
+        let moved: serde_json::Value = serde_json::from_str(&moved.unwrap()).unwrap();
+        assert_eq!(gitlab[0]["fingerprint"], moved[0]["fingerprint"]);
+    }
+
+    #[test]
+    fn test_is_all_pass() {
+        let checked = vec![Checked {
+            severity: Severity::Warning,
+            ..gen_checked("aaa", 0, 3, CheckedState::Fail, "", "")
+        }];
+        let path_checked = Printer::collect_by_path(checked);
+
+        assert!(Printer::is_all_pass(&path_checked, Severity::Error));
+        assert!(!Printer::is_all_pass(&path_checked, Severity::Warning));
+        assert!(!Printer::is_all_pass(&path_checked, Severity::Info));
+        assert_eq!(Printer::count_fail(&path_checked, Severity::Warning), 1);
+        assert_eq!(Printer::count_fail(&path_checked, Severity::Error), 0);
+    }
+
+    #[test]
+    fn test_line_index() {
+        let src = "aaa\r\nbbb\n\nccc";
+        let index = LineIndex::new(src, ColumnUnit::Byte);
+
+        assert_eq!(index.position(0), (1, 1));
+        assert_eq!(index.position(2), (1, 3));
+        assert_eq!(index.position(4), (1, 5));
+        assert_eq!(index.position(5), (2, 1));
+        assert_eq!(index.position(9), (3, 1));
+        assert_eq!(index.position(12), (4, 3));
+        assert_eq!(index.end_position(5, 9), (2, 5));
+        assert_eq!(index.end_position(5, 5), (2, 1));
+        assert_eq!(index.line_range(1), (0, 3));
+        assert_eq!(index.line_range(2), (5, 8));
+        assert_eq!(index.line_range(3), (9, 9));
+        assert_eq!(index.line_range(4), (10, 13));
+        assert_eq!(index.lines(), 4);
+        assert_eq!(LineIndex::new("aaa\nbbb\n", ColumnUnit::Byte).lines(), 2);
+        assert_eq!(LineIndex::new("", ColumnUnit::Byte).lines(), 1);
+    }
+
+    #[test]
+    fn test_column_unit() {
+        let src = "// \u{65e5}\u{672c}\u{8a9e}\u{1f600}\nif\n\u{1f600}if";
+        let pos = src.find("if").unwrap();
+        let last = src.rfind("if").unwrap();
+
+        let index = LineIndex::new(src, ColumnUnit::Char);
+        assert_eq!(index.position(0), (1, 1));
+        assert_eq!(index.position(3), (1, 4));
+        assert_eq!(index.position(pos), (2, 1));
+        assert_eq!(index.position(last), (3, 2));
+        assert_eq!(index.end_position(3, pos - 1), (1, 8));
+
+        let index = LineIndex::new(src, ColumnUnit::Utf16);
+        assert_eq!(index.position(last), (3, 3));
+        assert_eq!(index.end_position(3, pos - 1), (1, 9));
+
+        let index = LineIndex::new(src, ColumnUnit::Byte);
+        assert_eq!(index.position(last), (3, 5));
+        assert_eq!(index.end_position(3, pos - 1), (1, 17));
+    }
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width("abc", 0), 3);
+        assert_eq!(display_width("\u{65e5}\u{672c}", 0), 4);
+        assert_eq!(display_width("\ta", 0), 5);
+        assert_eq!(display_width("ab\t", 0), 4);
+        assert_eq!(display_width("\t", 2), 2);
+        assert_eq!(expand_tabs("a\tb\t"), "a   b   ");
+        assert_eq!(expand_tabs("\u{65e5}\tb"), "\u{65e5}  b");
+    }
+
+    fn render_pretty(src: &str, beg: usize, end: usize, before: usize, after: usize) -> String {
+        let index = LineIndex::new(src, ColumnUnit::Char);
+        let checked = gen_checked("eee", beg, end, CheckedState::Fail, "aaa", "ccc");
+        Printer::layout_pretty(Path::new("eee"), src, &index, &checked, before, after)
+            .into_iter()
+            .map(|(x, _)| x)
+            .collect()
+    }
+
+    #[test]
+    fn test_layout_pretty_span() {
+        let src = "aaa\nbbb ccc\nddd\n";
+        assert_eq!(
+            render_pretty(src, 4, 7, 0, 0),
+            ": aaa\n   --> eee:2:1\n  |\n2 | bbb ccc\n  | ^^^ hint: ccc\n\n"
+        );
+
+        // The underline reaches the last multibyte character
+        let src = "begin\n  abc \u{65e5}\u{672c} x";
+        assert_eq!(
+            render_pretty(src, 0, src.find(" x").unwrap(), 0, 0),
+            concat!(
+                ": aaa\n   --> eee:1:1\n  |\n",
+                "1 | / begin\n",
+                "2 | |   abc \u{65e5}\u{672c} x\n",
+                "  | |_________^ hint: ccc\n\n",
+            )
+        );
+
+        let src = "a begin\n  abc\n  end\n";
+        assert_eq!(
+            render_pretty(src, 2, 20, 0, 0),
+            concat!(
+                ": aaa\n   --> eee:1:3\n  |\n",
+                "1 |   a begin\n",
+                "  |  ___^\n",
+                "2 | |   abc\n",
+                "3 | |   end\n",
+                "  | |______^ hint: ccc\n\n",
+            )
+        );
+
+        // Lines in the middle are omitted
+        let src: String = (1..=12).map(|x| format!("l{}\n", x)).collect();
+        assert_eq!(
+            render_pretty(&src, 0, src.len() - 1, 0, 0),
+            concat!(
+                ": aaa\n   --> eee:1:1\n   |\n",
+                " 1 | / l1\n",
+                " 2 | | l2\n",
+                " 3 | | l3\n",
+                " 4 | | l4\n",
+                "   | | ...\n",
+                " 8 | | l8\n",
+                " 9 | | l9\n",
+                "10 | | l10\n",
+                "11 | | l11\n",
+                "12 | | l12\n",
+                "   | |___^ hint: ccc\n\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_layout_pretty_context() {
+        // Context lines are clamped at the file edges
+        let src = "aaa\nbbb ccc\nddd\n";
+        assert_eq!(
+            render_pretty(src, 4, 7, 5, 5),
+            concat!(
+                ": aaa\n   --> eee:2:1\n  |\n",
+                "1 | aaa\n",
+                "2 | bbb ccc\n",
+                "  | ^^^ hint: ccc\n",
+                "3 | ddd\n\n",
+            )
+        );
+        assert_eq!(
+            render_pretty(src, 0, 3, 0, 1),
+            ": aaa\n   --> eee:1:1\n  |\n1 | aaa\n  | ^^^ hint: ccc\n2 | bbb ccc\n\n"
+        );
+
+        // The empty span after the last line is shown even if it is out of the lines
+        assert_eq!(
+            render_pretty("aaa\n", 4, 4, 1, 1),
+            ": aaa\n   --> eee:2:1\n  |\n1 | aaa\n2 | \n  | ^ hint: ccc\n\n"
+        );
+
+        // Context lines of a multi-line span are indented by the bracket
+        let src = "x\na begin\n  abc\n  end\nzzz\n";
+        assert_eq!(
+            render_pretty(src, 4, 22, 1, 1),
+            concat!(
+                ": aaa\n   --> eee:2:3\n  |\n",
+                "1 |   x\n",
+                "2 |   a begin\n",
+                "  |  ___^\n",
+                "3 | |   abc\n",
+                "4 | |   end\n",
+                "  | |______^ hint: ccc\n",
+                "5 |   zzz\n\n",
+            )
+        );
+    }
 }
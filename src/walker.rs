@@ -0,0 +1,253 @@
+use crate::lint::normalize_path;
+use anyhow::{Context, Error};
+use glob::{MatchOptions, Pattern};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// -------------------------------------------------------------------------------------------------
+// Walker
+// -------------------------------------------------------------------------------------------------
+
+static IGNORE_FILES: &[&str] = &[".gitignore", ".ignore", ".flexlintignore"];
+static SKIP_DIRS: &[&str] = &[
+    // VCS
+    ".git",
+    ".hg",
+    ".svn",
+    ".bzr",
+    "_darcs",
+    // Build output
+    "target",
+    "node_modules",
+    "__pycache__",
+    "obj_dir",
+    "xsim.dir",
+    "simv.daidir",
+    "INCA_libs",
+];
+
+pub struct Walker {
+    no_ignore: bool,
+
+    /// Walk into hidden files/directories to match them by globs
+    pub hidden: bool,
+}
+
+impl Walker {
+    pub fn new(no_ignore: bool) -> Walker {
+        Walker {
+            no_ignore,
+            hidden: false,
+        }
+    }
+
+    /// Returns all files under `base` which are not ignored.
+    #[cfg_attr(tarpaulin, skip)]
+    pub fn walk(&self, base: &Path) -> Result<Vec<PathBuf>, Error> {
+        let mut ignores = Vec::new();
+        if !self.no_ignore {
+            // Ignore files in the parent directories are effective inside a repository only
+            let repo_root = base.ancestors().find(|x| x.join(".git").exists());
+            if let Some(repo_root) = repo_root {
+                let mut parents: Vec<_> = base
+                    .ancestors()
+                    .skip(1)
+                    .take_while(|x| x.starts_with(repo_root))
+                    .collect();
+                parents.reverse();
+                for dir in parents {
+                    ignores.append(&mut Ignore::load(dir)?);
+                }
+            }
+        }
+
+        let mut ret = Vec::new();
+        self.walk_dir(base, &mut ignores, &mut ret)?;
+        Ok(ret)
+    }
+
+    #[cfg_attr(tarpaulin, skip)]
+    fn walk_dir(
+        &self,
+        dir: &Path,
+        ignores: &mut Vec<Ignore>,
+        ret: &mut Vec<PathBuf>,
+    ) -> Result<(), Error> {
+        let len = ignores.len();
+        if !self.no_ignore {
+            ignores.append(&mut Ignore::load(dir)?);
+        }
+
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(dir)
+            .with_context(|| format!("failed to read: '{}'", dir.to_string_lossy()))?
+        {
+            entries.push(entry?);
+        }
+        entries.sort_by_key(|x| x.file_name());
+
+        for entry in entries {
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+
+            // Symbolic links to directories are not followed to avoid loops
+            let is_dir = file_type.is_dir();
+            let is_file = file_type.is_file() || (file_type.is_symlink() && path.is_file());
+
+            if !self.no_ignore {
+                let name = entry.file_name();
+                if !self.hidden && name.to_string_lossy().starts_with('.') {
+                    continue;
+                }
+                if is_dir && SKIP_DIRS.iter().any(|x| name == *x) {
+                    continue;
+                }
+            }
+
+            if Ignore::is_ignored(ignores, &path, is_dir) {
+                continue;
+            }
+
+            if is_dir {
+                self.walk_dir(&path, ignores, ret)?;
+            } else if is_file {
+                ret.push(path);
+            }
+        }
+
+        ignores.truncate(len);
+        Ok(())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// Ignore
+// -------------------------------------------------------------------------------------------------
+
+struct IgnoreRule {
+    pattern: Pattern,
+    negate: bool,
+    dir_only: bool,
+}
+
+struct Ignore {
+    dir: PathBuf,
+    rules: Vec<IgnoreRule>,
+}
+
+impl Ignore {
+    #[cfg_attr(tarpaulin, skip)]
+    fn load(dir: &Path) -> Result<Vec<Ignore>, Error> {
+        let mut ret = Vec::new();
+        for name in IGNORE_FILES {
+            let path = dir.join(name);
+            if path.is_file() {
+                let s = fs::read_to_string(&path)
+                    .with_context(|| format!("failed to open: '{}'", path.to_string_lossy()))?;
+                ret.push(Ignore::parse(dir, &s));
+            }
+        }
+        Ok(ret)
+    }
+
+    fn parse(dir: &Path, src: &str) -> Ignore {
+        let mut rules = Vec::new();
+        for line in src.lines() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (negate, line) = match line.strip_prefix('!') {
+                Some(x) => (true, x),
+                None => (false, line.strip_prefix('\\').unwrap_or(line)),
+            };
+            let (dir_only, line) = match line.strip_suffix('/') {
+                Some(x) => (true, x),
+                None => (false, line),
+            };
+
+            // Patterns without '/' match at any depth
+            let line = if line.contains('/') {
+                String::from(line.trim_start_matches('/'))
+            } else {
+                format!("**/{}", line)
+            };
+
+            if let Ok(pattern) = Pattern::new(&line) {
+                rules.push(IgnoreRule {
+                    pattern,
+                    negate,
+                    dir_only,
+                });
+            }
+        }
+
+        Ignore {
+            dir: normalize_path(dir),
+            rules,
+        }
+    }
+
+    fn matched(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        let path = normalize_path(path);
+        let path = path.strip_prefix(&self.dir).ok()?;
+        let options = MatchOptions {
+            case_sensitive: true,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+
+        // The last matched rule has priority
+        self.rules
+            .iter()
+            .rev()
+            .find(|x| (is_dir || !x.dir_only) && x.pattern.matches_path_with(path, options))
+            .map(|x| !x.negate)
+    }
+
+    fn is_ignored(ignores: &[Ignore], path: &Path, is_dir: bool) -> bool {
+        // Ignore files in deeper directories have priority
+        ignores
+            .iter()
+            .rev()
+            .find_map(|x| x.matched(path, is_dir))
+            .unwrap_or(false)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// Test
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static GITIGNORE: &str = r#"
+# comment
+*.log
+/build/
+sim/*.vcd
+!keep.log
+"#;
+
+    #[test]
+    fn test_ignore() {
+        let root = Ignore::parse(Path::new("repo"), GITIGNORE);
+        let sub = Ignore::parse(Path::new("repo/src"), "!debug.log\n");
+        let ignores = [root, sub];
+        let ignored = |x: &str, is_dir: bool| Ignore::is_ignored(&ignores, Path::new(x), is_dir);
+
+        assert!(ignored("repo/aaa.log", false));
+        assert!(ignored("repo/x/aaa.log", false));
+        assert!(!ignored("repo/keep.log", false));
+        assert!(!ignored("repo/src/debug.log", false));
+        assert!(ignored("repo/build", true));
+        assert!(!ignored("repo/build", false));
+        assert!(!ignored("repo/src/build", true));
+        assert!(ignored("repo/sim/a.vcd", false));
+        assert!(!ignored("repo/x/sim/a.vcd", false));
+        assert!(!ignored("repo/aaa.sv", false));
+    }
+}